@@ -1,9 +1,11 @@
 #![forbid(unsafe_code)]
 #![warn(clippy::pedantic)]
 
-use crate::{async_trait_alias::*, SCOPE};
+use super::{config::AuthConfig, oauth::default_scope, send_json};
+use crate::{async_trait_alias::AsyncSendSync, errors::DeviceCodeError};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CodeResponse {
@@ -19,6 +21,7 @@ pub struct CodeResponse {
 pub struct AuthenticationResponse {
     pub expires_in: u16,
     access_token: String,
+    refresh_token: String,
 }
 
 /// Defines expiry and token
@@ -28,62 +31,125 @@ pub struct CodeInfo {
     pub expires_in: u16,
     /// Provides token
     pub token: String,
+    /// Provides the refresh token
+    pub refresh_token: String,
 }
 
 pub fn device_authentication_code(
+    config: &AuthConfig,
     client_id: &str,
-) -> impl AsyncSendSync<Result<CodeResponse, reqwest::Error>> {
-    let request_url =
-        format!("https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode",);
-    let body = format!("client_id={}&scope={}", client_id, SCOPE);
-    let client = Client::new();
+) -> impl AsyncSendSync<Result<CodeResponse, DeviceCodeError>> {
+    let request_url = format!(
+        "{}/consumers/oauth2/v2.0/devicecode",
+        config.endpoints.microsoft
+    );
+    let request = config
+        .client
+        .post(request_url)
+        .form(&[("client_id", client_id), ("scope", &default_scope())]);
 
-    device_internal(client, request_url, body)
+    async move { Ok(send_json(request).await?) }
 }
 
-pub async fn device_internal(
-    client: Client,
-    request_url: String,
-    body: String,
-) -> Result<CodeResponse, reqwest::Error> {
-    let response = client.post(request_url).body(body).send().await?;
-
-    let response_data: CodeResponse = response.json().await?;
-
-    Ok(response_data)
+/// Error body returned by the token endpoint while the device code is not yet redeemed.
+#[derive(Debug, Deserialize)]
+struct PollError {
+    error: String,
+    error_description: Option<String>,
 }
 
+/// Polls the token endpoint until the user finishes signing in.
+///
+/// The endpoint is queried every `interval` seconds, backing off by five seconds whenever the
+/// server answers `slow_down`, and gives up once the code expires at `expires_at`.
 pub fn authenticate_device(
+    config: &AuthConfig,
     device_code: &str,
     client_id: &str,
-) -> impl AsyncSendSync<Result<CodeInfo, reqwest::Error>> {
-    let client = Client::new();
-    let request_url = format!("https://login.microsoftonline.com/common/consumers/v2.0/token",);
-
-    let body = format!(
-        "grant_type=urn:ietf:params:oauth:grant-type:device_code&client_id={}&device_code={}",
-        client_id, device_code
-    );
-
-    authenticate_internal(request_url, body, client)
+    interval: u16,
+    expires_at: Instant,
+) -> impl AsyncSendSync<Result<CodeInfo, DeviceCodeError>> {
+    let client = config.client.clone();
+    let request_url = format!("{}/consumers/oauth2/v2.0/token", config.endpoints.microsoft);
+
+    let form = vec![
+        (
+            "grant_type",
+            "urn:ietf:params:oauth:grant-type:device_code".to_string(),
+        ),
+        ("client_id", client_id.to_string()),
+        ("device_code", device_code.to_string()),
+    ];
+
+    authenticate_internal(
+        request_url,
+        form,
+        client,
+        Duration::from_secs(interval.into()),
+        expires_at,
+    )
 }
 
 async fn authenticate_internal(
     request_url: String,
-    body: String,
+    form: Vec<(&'static str, String)>,
     client: Client,
-) -> Result<CodeInfo, reqwest::Error> {
-    let request = client
-        .post(request_url)
-        .body(body)
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .send()
-        .await?;
-
-    let response_data: AuthenticationResponse = request.json().await?;
-
-    let expires_in = response_data.expires_in;
-    let token = response_data.access_token;
-
-    Ok(CodeInfo { expires_in, token })
+    mut interval: Duration,
+    deadline: Instant,
+) -> Result<CodeInfo, DeviceCodeError> {
+    loop {
+        tokio::time::sleep(interval).await;
+        if Instant::now() >= deadline {
+            return Err(DeviceCodeError::Expired);
+        }
+
+        let response = client
+            .post(&request_url)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|err| DeviceCodeError::ResponseError(err.to_string()))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|err| DeviceCodeError::ResponseError(err.to_string()))?;
+
+        if status.is_success() {
+            let response_data: AuthenticationResponse = serde_json::from_str(&text)
+                .map_err(|err| DeviceCodeError::ResponseError(err.to_string()))?;
+
+            return Ok(CodeInfo {
+                expires_in: response_data.expires_in,
+                token: response_data.access_token,
+                refresh_token: response_data.refresh_token,
+            });
+        }
+
+        let Ok(poll_error) = serde_json::from_str::<PollError>(&text) else {
+            return Err(DeviceCodeError::Status {
+                status: status.as_u16(),
+                body: text,
+            });
+        };
+
+        match poll_error.error.as_str() {
+            "authorization_pending" => {}
+            "slow_down" => interval += Duration::from_secs(5),
+            "expired_token" => return Err(DeviceCodeError::Expired),
+            "authorization_declined" | "access_denied" => return Err(DeviceCodeError::Declined),
+            "bad_verification_code" => {
+                return Err(DeviceCodeError::BadVerificationCode(
+                    poll_error.error_description.unwrap_or(poll_error.error),
+                ))
+            }
+            _ => {
+                return Err(DeviceCodeError::Status {
+                    status: status.as_u16(),
+                    body: text,
+                })
+            }
+        }
+    }
 }
@@ -0,0 +1,102 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use reqwest::Client;
+
+/// The base URLs of the services signed in against.
+///
+/// Each URL has no trailing slash, the paths of the individual endpoints are appended to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// The Microsoft identity platform, `https://login.microsoftonline.com`.
+    pub microsoft: String,
+    /// Microsoft account sign in as used by the official launcher, `https://login.live.com`.
+    pub live: String,
+    /// Xbox Live user authentication, `https://user.auth.xboxlive.com`.
+    pub xbox_user: String,
+    /// Xbox Live security token service, `https://xsts.auth.xboxlive.com`.
+    pub xbox_xsts: String,
+    /// Xbox Live device authentication, `https://device.auth.xboxlive.com`.
+    pub xbox_device: String,
+    /// Xbox Live single sign in, `https://sisu.xboxlive.com`.
+    pub xbox_sisu: String,
+    /// Minecraft services, `https://api.minecraftservices.com`.
+    pub minecraft_services: String,
+    /// The Minecraft session server, `https://sessionserver.mojang.com`.
+    pub session_server: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            microsoft: "https://login.microsoftonline.com".to_string(),
+            live: "https://login.live.com".to_string(),
+            xbox_user: "https://user.auth.xboxlive.com".to_string(),
+            xbox_xsts: "https://xsts.auth.xboxlive.com".to_string(),
+            xbox_device: "https://device.auth.xboxlive.com".to_string(),
+            xbox_sisu: "https://sisu.xboxlive.com".to_string(),
+            minecraft_services: "https://api.minecraftservices.com".to_string(),
+            session_server: "https://sessionserver.mojang.com".to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// Points every service at the same base URL, such as a local mock server.
+    #[must_use]
+    pub fn all(base: &str) -> Self {
+        let base = base.trim_end_matches('/');
+        Self {
+            microsoft: base.to_string(),
+            live: base.to_string(),
+            xbox_user: base.to_string(),
+            xbox_xsts: base.to_string(),
+            xbox_device: base.to_string(),
+            xbox_sisu: base.to_string(),
+            minecraft_services: base.to_string(),
+            session_server: base.to_string(),
+        }
+    }
+}
+
+/// Settings shared by every request made while signing in.
+///
+/// By default a new [`reqwest::Client`] is used with the public endpoints. Passing in your own
+/// client lets the sign in reuse its connection pool, user agent and timeouts.
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    pub(crate) client: Client,
+    pub(crate) endpoints: Endpoints,
+    pub(crate) xbox_identity: bool,
+}
+
+impl AuthConfig {
+    /// Creates a config using a new client and the public endpoints.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the HTTP client requests are sent with.
+    #[must_use]
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Sets the endpoints requests are sent to.
+    #[must_use]
+    pub fn endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Sets whether Java sign ins also fetch the user's Xbox Live identity, off by default.
+    ///
+    /// The identity takes an extra XSTS request, and a sign in fails if that request does.
+    #[must_use]
+    pub fn xbox_identity(mut self, fetch: bool) -> Self {
+        self.xbox_identity = fetch;
+        self
+    }
+}
@@ -1,4 +1,95 @@
 pub mod code;
+pub mod config;
 pub mod mojang;
 pub mod oauth;
+pub mod offline;
+pub mod session;
 pub mod xbox;
+pub mod yggdrasil;
+
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+
+use crate::errors::{
+    DeviceCodeError, MojangError, SessionError, TokenError, XTSError, XboxError, YggdrasilError,
+};
+
+/// A failed HTTP exchange, converted into the error of whichever stage made the request.
+pub enum HttpError {
+    /// The request could not be sent, or its response could not be read or parsed.
+    Response(String),
+    /// The server answered with an error status.
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+}
+
+/// Sends a request and parses its JSON response, keeping the status and body of error responses.
+pub async fn send_json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, HttpError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|err| HttpError::Response(err.to_string()))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|err| HttpError::Response(err.to_string()))?;
+
+    if !status.is_success() {
+        return Err(HttpError::Status {
+            status: status.as_u16(),
+            body,
+        });
+    }
+
+    serde_json::from_str(&body)
+        .map_err(|err| HttpError::Response(format!("Unexpected response ({err}): {body}")))
+}
+
+/// Sends a request whose successful response has no body, keeping the status and body of error responses.
+pub async fn send_empty(request: RequestBuilder) -> Result<(), HttpError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|err| HttpError::Response(err.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|err| HttpError::Response(err.to_string()))?;
+
+    Err(HttpError::Status {
+        status: status.as_u16(),
+        body,
+    })
+}
+
+macro_rules! from_http_error {
+    ($($error:ty),*) => {$(
+        impl From<HttpError> for $error {
+            fn from(err: HttpError) -> Self {
+                match err {
+                    HttpError::Response(message) => Self::ResponseError(message),
+                    HttpError::Status { status, body } => Self::Status { status, body },
+                }
+            }
+        }
+    )*};
+}
+
+from_http_error!(
+    DeviceCodeError,
+    MojangError,
+    SessionError,
+    TokenError,
+    XTSError,
+    XboxError,
+    YggdrasilError
+);
@@ -4,15 +4,15 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::error::Error;
 
-use crate::async_trait_alias::AsyncSendSync;
+use super::{config::AuthConfig, send_json, xbox::XboxIdentity, HttpError};
+use crate::{async_trait_alias::AsyncSendSync, errors::MojangError};
 
 /// Defines the custom authentication data received from Mojang.
 ///
 /// This struct contains the necessary information for authenticating a user with Mojang's services,
-/// including the access token for API requests, the user's UUID, the token's expiry time, and an optional
-/// XTS token for Bedrock Realms.
+/// including the access token for API requests, the user's UUID, the token's expiry time, and the player's
+/// profile and entitlements.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct AuthInfo {
     /// The bearer token received from Mojang.
@@ -24,9 +24,8 @@ pub struct AuthInfo {
 
     /// The UUID of the authenticated user.
     ///
-    /// This UUID is not the player's Minecraft UUID but a unique identifier generated by Mojang for
-    /// authentication purposes. It is used for launching the game and should be treated as sensitive
-    /// information.
+    /// This is the player's Minecraft UUID, taken from their profile, and is what the game's `--uuid`
+    /// argument expects.
     pub uuid: String,
 
     /// The expiry time of the access token in seconds.
@@ -35,55 +34,245 @@ pub struct AuthInfo {
     /// and a new one must be obtained to continue making authenticated requests.
     pub expires_in: i32,
 
-    /// The XTS token for Bedrock Realms.
+    /// The Minecraft profile of the authenticated user.
     ///
-    /// If the user is authenticating for Bedrock Realms, this field will contain the XTS token. This token
-    /// is used specifically for accessing Bedrock Realms features and is only present if the user is
-    /// authenticating for Bedrock Realms.
-    pub xts_token: Option<String>,
+    /// This holds the player's name, which is what the game's `--username` argument expects, along
+    /// with their skins and capes.
+    pub profile: MinecraftProfile,
+
+    /// The Xbox Live identity of the user, if [`crate::AuthConfig::xbox_identity`] asked for it.
+    ///
+    /// This holds the user's gamertag and age group, for showing in an account picker or gating
+    /// features on.
+    #[serde(default)]
+    pub xbox_identity: Option<XboxIdentity>,
+
+    /// The games and products the user is entitled to.
+    pub entitlements: Entitlements,
+}
+
+/// A player's Minecraft profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MinecraftProfile {
+    /// The player's UUID, without dashes.
+    pub id: String,
+    /// The player's name.
+    pub name: String,
+    /// The skins on the player's account.
+    #[serde(default)]
+    pub skins: Vec<Skin>,
+    /// The capes on the player's account.
+    #[serde(default)]
+    pub capes: Vec<Cape>,
+}
+
+/// A skin on a player's account.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Skin {
+    /// The skin's id.
+    pub id: String,
+    /// Whether the skin is `ACTIVE` or `INACTIVE`.
+    pub state: String,
+    /// Where the skin texture can be downloaded.
+    pub url: String,
+    /// The skin model, either `CLASSIC` or `SLIM`.
+    pub variant: String,
+    /// The hash of the skin texture.
+    #[serde(default)]
+    pub texture_key: Option<String>,
+    /// The name of a default skin, such as `STEVE`.
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+/// A cape on a player's account.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Cape {
+    /// The cape's id.
+    pub id: String,
+    /// Whether the cape is `ACTIVE` or `INACTIVE`.
+    pub state: String,
+    /// Where the cape texture can be downloaded.
+    pub url: String,
+    /// The name of the cape, such as `Migrator`.
+    pub alias: String,
+}
+
+/// The games and products an account is entitled to, as signed by Mojang.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entitlements {
+    /// The individual entitlements.
+    #[serde(default)]
+    pub items: Vec<Entitlement>,
+    /// A JWT signed by Mojang covering every entitlement.
+    #[serde(default)]
+    pub signature: String,
+    /// The id of the key the signature was made with.
+    #[serde(default)]
+    pub key_id: String,
+}
+
+impl Entitlements {
+    /// Returns `true` if the account may play Java Edition.
+    ///
+    /// This is the case for accounts that bought the game as well as for Xbox Game Pass members,
+    /// whose subscription grants the same entitlements.
+    #[must_use]
+    pub fn owns_java(&self) -> bool {
+        self.items.iter().any(|item| {
+            matches!(
+                item.kind(),
+                EntitlementKind::ProductMinecraft | EntitlementKind::GameMinecraft
+            )
+        })
+    }
+
+    /// Returns `true` if the account's entitlements come from an Xbox Game Pass subscription.
+    #[must_use]
+    pub fn game_pass(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| item.kind() == EntitlementKind::GamePass)
+    }
+}
+
+/// A single entitlement.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Entitlement {
+    /// The entitlement's name, such as `game_minecraft`.
+    pub name: String,
+    /// A JWT signed by Mojang covering this entitlement.
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl Entitlement {
+    /// Returns what the entitlement grants.
+    #[must_use]
+    pub fn kind(&self) -> EntitlementKind {
+        match self.name.as_str() {
+            "product_minecraft" => EntitlementKind::ProductMinecraft,
+            "game_minecraft" => EntitlementKind::GameMinecraft,
+            "product_minecraft_bedrock" => EntitlementKind::ProductMinecraftBedrock,
+            "game_minecraft_bedrock" => EntitlementKind::GameMinecraftBedrock,
+            "product_dungeons" => EntitlementKind::ProductDungeons,
+            "game_dungeons" => EntitlementKind::GameDungeons,
+            "product_legends" => EntitlementKind::ProductLegends,
+            "game_legends" => EntitlementKind::GameLegends,
+            name if name.starts_with("product_game_pass") => EntitlementKind::GamePass,
+            _ => EntitlementKind::Other,
+        }
+    }
+}
+
+/// What an [`Entitlement`] grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntitlementKind {
+    /// The Java Edition product.
+    ProductMinecraft,
+    /// The Java Edition game.
+    GameMinecraft,
+    /// The Bedrock Edition product.
+    ProductMinecraftBedrock,
+    /// The Bedrock Edition game.
+    GameMinecraftBedrock,
+    /// The Minecraft Dungeons product.
+    ProductDungeons,
+    /// The Minecraft Dungeons game.
+    GameDungeons,
+    /// The Minecraft Legends product.
+    ProductLegends,
+    /// The Minecraft Legends game.
+    GameLegends,
+    /// An Xbox Game Pass subscription, such as `product_game_pass_ultimate`.
+    GamePass,
+    /// An entitlement this crate doesn't know about.
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
 struct MojangResponse {
-    username: String,
     access_token: String,
     token_type: String,
     expires_in: i32,
 }
 
 pub fn token(
+    config: &AuthConfig,
     userhash: &str,
     xsts_token: &str,
-) -> impl AsyncSendSync<Result<AuthInfo, Box<dyn Error>>> {
-    let client = Client::new();
-    let identity_token = format!("XBL3.0 x={};{}", userhash, xsts_token);
+) -> impl AsyncSendSync<Result<AuthInfo, MojangError>> {
+    let client = config.client.clone();
+    let services = config.endpoints.minecraft_services.clone();
+    let identity_token = format!("XBL3.0 x={userhash};{xsts_token}");
     let body = json!({
         "identityToken": identity_token
     });
-    tokeninternal(client, body)
+    tokeninternal(client, services, body)
 }
 
-async fn tokeninternal(client: Client, body: Value) -> Result<AuthInfo, Box<dyn Error>> {
-    let res = client
-        .post("https://api.minecraftservices.com/authentication/login_with_xbox")
-        .body(body.to_string())
-        .send()
-        .await?;
-
-    let response: MojangResponse = res.json().await?;
+async fn tokeninternal(
+    client: Client,
+    services: String,
+    body: Value,
+) -> Result<AuthInfo, MojangError> {
+    let request = client
+        .post(format!("{services}/authentication/login_with_xbox"))
+        .json(&body);
+    let response: MojangResponse = send_json(request).await?;
 
     if response.token_type != "Bearer" {
-        println!("Sorry, we ran into an error in authentication.");
-        return Err("Invalid token type".into());
+        return Err(MojangError::ResponseError(format!(
+            "Invalid token type: {}",
+            response.token_type
+        )));
+    }
+    let entitlements = entitlements(&client, &services, &response.access_token).await?;
+    if !entitlements.owns_java() {
+        return Err(MojangError::NotOwned {
+            access_token: response.access_token,
+            expires_in: response.expires_in,
+        });
     }
-    let access_token = response.access_token;
-    let uuid = response.username;
-    let expires_in = response.expires_in; // Ensure this is correctly set to the expiration time
+    let profile = profile(&client, &services, &response.access_token).await?;
 
     Ok(AuthInfo {
-        uuid: uuid,
-        access_token: access_token,
-        expires_in: expires_in, // This should correctly reflect the expiration time
-        xts_token: None,        // Assuming this is handled elsewhere or not relevant for this issue
+        uuid: profile.id.clone(),
+        access_token: response.access_token,
+        expires_in: response.expires_in,
+        profile,
+        xbox_identity: None,
+        entitlements,
     })
 }
+
+/// Fetches the Minecraft profile of the player the access token belongs to.
+async fn profile(
+    client: &Client,
+    services: &str,
+    access_token: &str,
+) -> Result<MinecraftProfile, MojangError> {
+    let request = client
+        .get(format!("{services}/minecraft/profile"))
+        .bearer_auth(access_token);
+
+    match send_json(request).await {
+        Err(HttpError::Status { status: 404, .. }) => Err(MojangError::NoProfile),
+        result => Ok(result?),
+    }
+}
+
+/// Fetches the games and products the account is entitled to.
+async fn entitlements(
+    client: &Client,
+    services: &str,
+    access_token: &str,
+) -> Result<Entitlements, MojangError> {
+    let request = client
+        .get(format!("{services}/entitlements/mcstore"))
+        .bearer_auth(access_token);
+
+    Ok(send_json(request).await?)
+}
@@ -1,12 +1,21 @@
 #![forbid(unsafe_code, missing_docs)]
 #![warn(clippy::pedantic)]
 
-use reqwest::Client;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use reqwest::RequestBuilder;
+use ring::digest;
 use serde::Deserialize;
-use tokio::{io::AsyncReadExt, net::TcpListener, sync::mpsc};
+use std::{ops::RangeInclusive, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
 
+use super::{config::AuthConfig, send_json};
 use crate::{
-    async_trait_alias::*,
+    async_trait_alias::{AsyncSend, AsyncSendSync},
     errors::{OAuthError, TokenError},
     SCOPE,
 };
@@ -34,164 +43,435 @@ pub struct Token {
     pub refresh_token: String,
 }
 
-pub fn server(port: u16) -> Result<impl AsyncSendSync<Result<Info, OAuthError>>, OAuthError> {
-    let (tx, mut rx) = mpsc::channel::<Info>(1);
-
-    let server = tokio::spawn(async move {
-        match TcpListener::bind(format!("127.0.0.1:{}", port)).await {
-            Ok(listener) => {
-                loop {
-                    match listener.accept().await {
-                        Ok((mut socket, _)) => {
-                            let tx = tx.clone();
-                            tokio::spawn(async move {
-                                let mut buf = [0; 1024];
-                                loop {
-                                    let n = match socket.read(&mut buf).await {
-                                        Ok(n) if n == 0 => break,
-                                        Ok(n) => n,
-                                        Err(e) => {
-                                            eprintln!("failed to read from socket; err = {:?}", e);
-                                            break;
-                                        }
-                                    };
-
-                                    // Here you would parse the received data into your `Info` struct
-                                    // For demonstration, let's assume we have a function `parse_info` that does this
-                                    match parse_info(&buf[..n]) {
-                                        Ok(info) => {
-                                            if let Err(e) = tx.try_send(info) {
-                                                eprintln!(
-                                                    "failed to send data to channel; err = {:?}",
-                                                    e
-                                                );
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!("failed to parse info; err = {:?}", e);
-                                        }
-                                    }
-                                }
-                            });
-                        }
-                        Err(e) => {
-                            eprintln!("failed to accept connection; err = {:?}", e);
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("failed to bind listener; err = {:?}", e);
+/// The page the loopback server answers the browser with once the sign in callback arrives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallbackPage {
+    /// Responds with the given HTML document.
+    Html(String),
+    /// Redirects the browser to the given URL.
+    Redirect(String),
+}
+
+impl CallbackPage {
+    /// The page shown by default after a successful sign in.
+    #[must_use]
+    pub fn default_success() -> Self {
+        Self::Html(
+            "<!DOCTYPE html><html><head><title>Signed in</title></head><body><h1>Signed in</h1><p>You can close this tab and return to the launcher.</p></body></html>"
+                .to_string(),
+        )
+    }
+
+    /// The page shown by default after a failed sign in.
+    #[must_use]
+    pub fn default_failure() -> Self {
+        Self::Html(
+            "<!DOCTYPE html><html><head><title>Sign in failed</title></head><body><h1>Sign in failed</h1><p>Return to the launcher and try again.</p></body></html>"
+                .to_string(),
+        )
+    }
+
+    fn response(&self) -> String {
+        match self {
+            Self::Html(html) => format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{html}",
+                html.len()
+            ),
+            Self::Redirect(url) => format!(
+                "HTTP/1.1 302 Found\r\nLocation: {url}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            ),
+        }
+    }
+}
+
+const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const BAD_REQUEST: &str =
+    "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// The largest request head the loopback server accepts.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// The parts of an HTTP request line the loopback server cares about.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RequestLine<'a> {
+    /// The request method, such as `GET`.
+    pub method: &'a str,
+    /// The path of the request target.
+    pub path: &'a str,
+    /// The query string of the request target, without the leading `?`.
+    pub query: Option<&'a str>,
+}
+
+/// Parses the request line of an HTTP/1.x request, such as `GET /?code=abc HTTP/1.1`.
+pub fn parse_request_line(request: &str) -> Result<RequestLine<'_>, OAuthError> {
+    let line = request.lines().next().unwrap_or_default();
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(OAuthError::ParseError(format!(
+            "Malformed request line: {line}"
+        )));
+    };
+
+    if method.is_empty() || !target.starts_with('/') || !version.starts_with("HTTP/1.") {
+        return Err(OAuthError::ParseError(format!(
+            "Malformed request line: {line}"
+        )));
+    }
+
+    let target = target.split_once('#').map_or(target, |(target, _)| target);
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+
+    Ok(RequestLine {
+        method,
+        path,
+        query,
+    })
+}
+
+/// Parses the query string of the authorization callback.
+pub fn parse_info(query: &str) -> Result<Info, OAuthError> {
+    serde_urlencoded::from_str(query).map_err(|err| OAuthError::ParseError(err.to_string()))
+}
+
+/// Which ports the loopback server may listen on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedirectPort {
+    /// Listens on exactly this port.
+    Fixed(u16),
+    /// Lets the operating system pick a free port.
+    Ephemeral,
+    /// Listens on the first free port in this range.
+    Range(RangeInclusive<u16>),
+}
+
+impl RedirectPort {
+    /// Binds a listener on `127.0.0.1` according to this mode.
+    pub(crate) fn bind(self) -> Result<std::net::TcpListener, OAuthError> {
+        let ports = match self {
+            Self::Fixed(port) => port..=port,
+            Self::Ephemeral => 0..=0,
+            Self::Range(ports) => ports,
+        };
+
+        let mut last_error = None;
+        for port in ports {
+            match std::net::TcpListener::bind(("127.0.0.1", port)) {
+                Ok(listener) => return Ok(listener),
+                Err(err) => last_error = Some(err),
             }
         }
-    });
-
-    Ok(async move {
-        let info = rx.recv().await.expect("server did not receive params");
-
-        if info.error.as_ref().map_or(false, |s| !s.is_empty())
-            && info
-                .error_description
-                .as_ref()
-                .map_or(false, |s| !s.is_empty())
-        {
-            let err = OAuthError::AuthenticationFailure(info.error_description.unwrap());
-            Err(err)
-        } else {
-            server.abort();
-
-            Ok(info)
+
+        Err(OAuthError::BindError(last_error.map_or_else(
+            || "No ports to listen on".to_string(),
+            |err| err.to_string(),
+        )))
+    }
+}
+
+/// Where the loopback server listens.
+pub enum Listener {
+    /// Binds this port when the server starts.
+    Port(u16),
+    /// Uses a listener bound ahead of time.
+    Bound(std::net::TcpListener),
+}
+
+impl Listener {
+    async fn bind(self) -> std::io::Result<TcpListener> {
+        match self {
+            Self::Port(port) => TcpListener::bind(("127.0.0.1", port)).await,
+            Self::Bound(listener) => {
+                listener.set_nonblocking(true)?;
+                TcpListener::from_std(listener)
+            }
         }
-    })
+    }
 }
 
-fn parse_info(data: &[u8]) -> Result<Info, OAuthError> {
-    let data_str = std::str::from_utf8(data)
-        .map_err(|_| OAuthError::ParseError("Invalid UTF-8".to_string()))?;
+/// Waits for the authorization callback on `127.0.0.1`.
+///
+/// The listener, and every connection it accepted, is closed as soon as the returned future
+/// completes, times out or is dropped.
+pub fn server(
+    listener: Listener,
+    state: String,
+    path: String,
+    success_page: CallbackPage,
+    failure_page: CallbackPage,
+    timeout: Option<Duration>,
+) -> impl AsyncSend<Result<Info, OAuthError>> {
+    let pages = Arc::new((state, path, success_page, failure_page));
 
-    // Extract the query string from the HTTP request
-    let mut query_start = None;
-    while query_start.is_none() {
-        query_start = data_str.find('?');
+    async move {
+        let listener = listener
+            .bind()
+            .await
+            .map_err(|err| OAuthError::BindError(err.to_string()))?;
+
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, listen(listener, pages))
+                .await
+                .map_err(|_| OAuthError::Timeout)?,
+            None => listen(listener, pages).await,
+        }
     }
-    let query_start =
-        query_start.ok_or_else(|| OAuthError::ParseError("No query string found".to_string()))?;
-    let query_end = data_str.find('#').unwrap_or_else(|| data_str.len());
-    let query_string = &data_str[query_start + 1..query_end];
-
-    // Parse the query string directly
-    let query_params: Vec<(String, String)> = url::form_urlencoded::parse(query_string.as_bytes())
-        .into_owned()
-        .collect();
-
-    // Extract the 'code', 'state', 'error', and 'error_description' parameters
-    let code = query_params
-        .iter()
-        .find_map(|(k, v)| if k == "code" { Some(v.clone()) } else { None });
-    let state = query_params.iter().find_map(|(k, v)| {
-        if k == "state" {
-            // Find the position of "HTTP/1.1\r\n" in the state value
-            let http_start = v.find(" HTTP/1.1\r\n");
-            // If found, slice the string up to that position
-            http_start.map(|pos| v[..pos].to_string())
-        } else {
-            None
+}
+
+async fn listen(
+    listener: TcpListener,
+    pages: Arc<(String, String, CallbackPage, CallbackPage)>,
+) -> Result<Info, OAuthError> {
+    // Dropping the set aborts any connection still being served.
+    let mut connections = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                // Accept errors only affect that one connection, so keep waiting for the callback.
+                if let Ok((socket, _)) = accepted {
+                    connections.spawn(respond(socket, Arc::clone(&pages)));
+                }
+            }
+            Some(Ok(Some(result))) = connections.join_next() => return result,
         }
-    });
-    let error = query_params
-        .iter()
-        .find_map(|(k, v)| if k == "error" { Some(v.clone()) } else { None });
-    let error_description = query_params.iter().find_map(|(k, v)| {
-        if k == "error_description" {
-            Some(v.clone())
-        } else {
-            None
+    }
+}
+
+/// Serves a single connection, returning the callback result if the request was the callback.
+async fn respond(
+    mut socket: TcpStream,
+    pages: Arc<(String, String, CallbackPage, CallbackPage)>,
+) -> Option<Result<Info, OAuthError>> {
+    let Some(request) = read_request(&mut socket).await else {
+        let _ = socket.write_all(BAD_REQUEST.as_bytes()).await;
+        let _ = socket.shutdown().await;
+        return None;
+    };
+
+    let (response, result) = match parse_request_line(&request) {
+        // Browsers also ask for things like `/favicon.ico`, which aren't the callback.
+        Ok(line) if line.method != "GET" || line.path != pages.1 => (NOT_FOUND.to_string(), None),
+        Ok(line) => {
+            let (state, _, success_page, failure_page) = &*pages;
+            let result =
+                parse_info(line.query.unwrap_or_default()).and_then(|info| check_info(info, state));
+            let page = if result.is_ok() {
+                success_page
+            } else {
+                failure_page
+            };
+            (page.response(), Some(result))
         }
-    });
-
-    // Construct the Info struct
-    let info = Info {
-        code,
-        state,
-        error,
-        error_description,
+        Err(_) => (BAD_REQUEST.to_string(), None),
     };
+
+    // The callback result stands even if the browser closed the connection before the page arrived.
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+
+    result
+}
+
+/// Reads the request head, which may arrive across several packets.
+async fn read_request(socket: &mut TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        if request.len() > MAX_REQUEST_SIZE {
+            return None;
+        }
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    String::from_utf8(request).ok()
+}
+
+/// Returns the query of a redirect URL, or the string itself if it is already just a query.
+pub fn redirect_query(redirect: &str) -> &str {
+    let query = redirect
+        .split_once('?')
+        .map_or(redirect, |(_, query)| query);
+
+    query.split_once('#').map_or(query, |(query, _)| query)
+}
+
+/// Rejects callbacks with a missing or mismatched state, or that carry an error.
+pub fn check_info(info: Info, state: &str) -> Result<Info, OAuthError> {
+    if info.state.as_deref() != Some(state) {
+        return Err(OAuthError::StateMismatch);
+    }
+
+    if let Some(error) = info.error.as_ref().filter(|error| !error.is_empty()) {
+        let description = info
+            .error_description
+            .clone()
+            .filter(|description| !description.is_empty())
+            .unwrap_or_else(|| error.clone());
+        return Err(OAuthError::AuthenticationFailure(description));
+    }
+
     Ok(info)
 }
 
+/// Generates a random `state` value used to tie the authorization callback to this sign in.
+pub fn state() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Generates a PKCE code verifier and its S256 code challenge.
+pub fn pkce() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code_verifier = URL_SAFE_NO_PAD.encode(bytes);
+    let code_challenge =
+        URL_SAFE_NO_PAD.encode(digest::digest(&digest::SHA256, code_verifier.as_bytes()));
+
+    (code_verifier, code_challenge)
+}
+
+/// The parts of a sign in its authorization URL and token requests must agree on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    /// The client ID of the app.
+    pub client_id: String,
+    /// The tenant signed in to, `consumers` for personal Microsoft accounts.
+    pub tenant: String,
+    /// The space separated scopes.
+    pub scope: String,
+}
+
+impl Params {
+    /// Signs personal Microsoft accounts in for Xbox Live.
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            tenant: default_tenant(),
+            scope: default_scope(),
+        }
+    }
+
+    /// Returns the scopes encoded for the authorization URL, separated by `%20`.
+    pub fn scope_query(&self) -> String {
+        self.scope
+            .split(' ')
+            .map(encode)
+            .collect::<Vec<_>>()
+            .join("%20")
+    }
+
+    /// Builds a form request to the token endpoint for `grant`.
+    fn token_request(
+        &self,
+        config: &AuthConfig,
+        client_secret: Option<&str>,
+        grant: &[(&str, &str)],
+    ) -> RequestBuilder {
+        let mut form = vec![
+            ("client_id", self.client_id.as_str()),
+            ("scope", &self.scope),
+        ];
+        form.extend_from_slice(grant);
+        if let Some(client_secret) = client_secret {
+            form.push(("client_secret", client_secret));
+        }
+
+        config
+            .client
+            .post(format!(
+                "{}/{}/oauth2/v2.0/token",
+                config.endpoints.microsoft, self.tenant
+            ))
+            .form(&form)
+    }
+}
+
+/// The tenant personal Microsoft accounts sign in to.
+pub fn default_tenant() -> String {
+    "consumers".to_string()
+}
+
+/// The space separated scopes needed to sign in to Xbox Live and keep a refresh token.
+pub fn default_scope() -> String {
+    SCOPE.replace("%20", " ")
+}
+
+/// Which prompt Microsoft shows the user when they open the authorization URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    /// Lets the user pick an account, instead of silently using the one signed in to the browser.
+    SelectAccount,
+    /// Asks for the user's credentials, even if they are already signed in.
+    Login,
+    /// Asks the user to grant the app's permissions again.
+    Consent,
+}
+
+impl Prompt {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::SelectAccount => "select_account",
+            Self::Login => "login",
+            Self::Consent => "consent",
+        }
+    }
+}
+
+/// URL encodes a query or form value.
+pub fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
 pub fn token(
+    config: &AuthConfig,
     code: &str,
-    client_id: &str,
-    port: u16,
-    client_secret: &str,
+    params: &Params,
+    redirect_uri: &str,
+    client_secret: Option<&str>,
+    code_verifier: &str,
 ) -> impl AsyncSendSync<Result<Token, TokenError>> {
-    let url = format!("https://login.microsoftonline.com/consumers/oauth2/v2.0/token");
-    let client = Client::new();
-    let body = format!(
-      "client_id={}&scope={}&redirect_uri=http://localhost:{}&grant_type=authorization_code&code={}&client_secret={}", 
-      client_id, SCOPE, port, code, client_secret);
+    let request = params.token_request(
+        config,
+        client_secret,
+        &[
+            ("redirect_uri", redirect_uri),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("code_verifier", code_verifier),
+        ],
+    );
 
-    async move {
-        'out: {
-            let result = client.post(url).body(body).send().await;
-
-            let std::result::Result::Ok(response) = result else {
-                println!("Part 1");
-                break 'out Err(TokenError::ResponseError(
-                    "Failed to send request".to_string(),
-                ));
-            };
+    async move { Ok(send_json(request).await?) }
+}
 
-            let text = response
-                .text()
-                .await
-                .map_err(|_| TokenError::ResponseError("Failed to send request".to_string()))?;
-            let std::result::Result::Ok(token) = serde_json::from_str::<Token>(&text) else {
-                break 'out Err(TokenError::ResponseError(
-                    "Failed to send request, Check your Client Secret.".to_string(),
-                ));
-            };
-            std::result::Result::Ok(token)
-        }
-    }
+/// Redeems a refresh token for a new access token and a rotated refresh token.
+///
+/// Public clients, such as the device code flow, don't have a client secret and pass `None`.
+pub fn refresh(
+    config: &AuthConfig,
+    refresh_token: &str,
+    params: &Params,
+    client_secret: Option<&str>,
+) -> impl AsyncSendSync<Result<Token, TokenError>> {
+    let request = params.token_request(
+        config,
+        client_secret,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ],
+    );
+
+    async move { Ok(send_json(request).await?) }
 }
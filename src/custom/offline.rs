@@ -0,0 +1,40 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use std::fmt::Write;
+
+use crate::errors::OfflineError;
+
+/// Checks a username against the rules Minecraft applies to player names.
+pub fn validate(username: &str) -> Result<(), OfflineError> {
+    // Checking the characters first means the name is ASCII by the time its length is checked,
+    // so its length in bytes is its length in characters.
+    if let Some(char) = username
+        .chars()
+        .find(|char| !char.is_ascii_alphanumeric() && *char != '_')
+    {
+        return Err(OfflineError::InvalidCharacter(char));
+    }
+    if !(3..=16).contains(&username.len()) {
+        return Err(OfflineError::InvalidLength(username.len()));
+    }
+
+    Ok(())
+}
+
+/// Derives the UUID an offline mode server gives a player, without dashes.
+///
+/// This matches Java's `UUID.nameUUIDFromBytes` of `OfflinePlayer:<username>`, a name based
+/// version 3 UUID, which is what the vanilla server uses.
+pub fn uuid(username: &str) -> String {
+    let mut bytes = md5::compute(format!("OfflinePlayer:{username}")).0;
+    bytes[6] = bytes[6] & 0x0f | 0x30;
+    bytes[8] = bytes[8] & 0x3f | 0x80;
+
+    bytes
+        .iter()
+        .fold(String::with_capacity(32), |mut uuid, byte| {
+            let _ = write!(uuid, "{byte:02x}");
+            uuid
+        })
+}
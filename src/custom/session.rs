@@ -0,0 +1,117 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{
+    mojang::{AuthInfo, Entitlements, MinecraftProfile},
+    oauth::{self, Params},
+    xbox::{TokenKind, XboxIdentity},
+};
+
+/// A persistable authentication session.
+///
+/// Unlike [`AuthInfo`], every expiry in this struct is an absolute Unix timestamp in seconds, and the
+/// Microsoft refresh token is kept, so a session can be saved to disk and resumed later by refreshing it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AuthSession {
+    /// The Minecraft bearer token used to launch the game.
+    pub access_token: String,
+
+    /// The UUID of the authenticated user.
+    pub uuid: String,
+
+    /// The Minecraft profile of the authenticated user.
+    pub profile: MinecraftProfile,
+
+    /// The Xbox Live identity of the authenticated user, if [`crate::AuthConfig::xbox_identity`] asked for it.
+    #[serde(default)]
+    pub xbox_identity: Option<XboxIdentity>,
+
+    /// The games and products the user is entitled to.
+    pub entitlements: Entitlements,
+
+    /// The Microsoft refresh token used to resume the session.
+    pub refresh_token: String,
+
+    /// Where the refresh token was issued, [`TokenKind::Live`] for [`crate::LiveLogin`] sessions.
+    /// Sessions saved before this was recorded were all issued to an Azure app.
+    #[serde(default)]
+    pub token_kind: TokenKind,
+
+    /// The tenant the refresh token was issued by, `consumers` unless [`crate::OauthBuilder::tenant`] changed it.
+    #[serde(default = "oauth::default_tenant")]
+    pub tenant: String,
+
+    /// The space separated scopes the refresh token was issued for.
+    #[serde(default = "oauth::default_scope")]
+    pub scope: String,
+
+    /// When the Microsoft access token expires.
+    pub msa_expires_at: u64,
+
+    /// When the Xbox Live user token expires.
+    pub xbl_expires_at: u64,
+
+    /// When the XSTS token expires.
+    pub xsts_expires_at: u64,
+
+    /// When the Minecraft bearer token expires.
+    pub minecraft_expires_at: u64,
+}
+
+impl AuthSession {
+    /// Returns `true` once the Minecraft bearer token has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        now() >= self.minecraft_expires_at
+    }
+
+    /// Returns `true` if the Minecraft bearer token expires within `margin`.
+    ///
+    /// Launchers should refresh the session when this returns `true` rather than waiting for
+    /// [`AuthSession::is_expired`], so the game isn't started with a token about to run out.
+    #[must_use]
+    pub fn needs_refresh(&self, margin: Duration) -> bool {
+        now().saturating_add(margin.as_secs()) >= self.minecraft_expires_at
+    }
+
+    /// Records the tenant and scopes of the sign in the session came from.
+    pub(crate) fn with_params(mut self, params: &Params) -> Self {
+        self.tenant.clone_from(&params.tenant);
+        self.scope.clone_from(&params.scope);
+        self
+    }
+
+    /// Returns the parameters to refresh the session with, as the app with `client_id`.
+    pub(crate) fn params(&self, client_id: &str) -> Params {
+        Params {
+            client_id: client_id.to_string(),
+            tenant: self.tenant.clone(),
+            scope: self.scope.clone(),
+        }
+    }
+
+    /// Returns the session as [`AuthInfo`], with `expires_in` counted from now.
+    #[must_use]
+    pub fn auth_data(&self) -> AuthInfo {
+        let remaining = self.minecraft_expires_at.saturating_sub(now());
+
+        AuthInfo {
+            access_token: self.access_token.clone(),
+            uuid: self.uuid.clone(),
+            expires_in: i32::try_from(remaining).unwrap_or(i32::MAX),
+            profile: self.profile.clone(),
+            xbox_identity: self.xbox_identity.clone(),
+            entitlements: self.entitlements.clone(),
+        }
+    }
+}
+
+/// The current time as a Unix timestamp in seconds.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
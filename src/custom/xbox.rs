@@ -1,18 +1,29 @@
 #![forbid(unsafe_code, missing_docs)]
 #![warn(clippy::pedantic)]
 
-use crate::async_trait_alias::*;
-use reqwest::{
-    header::{self, HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE},
-    Client,
-};
-use serde::Deserialize;
-use serde_json::{json, Value};
+use crate::async_trait_alias::AsyncSendSync;
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
+use super::{config::AuthConfig, send_json, HttpError};
 use crate::errors::{XTSError, XboxError};
+
 #[derive(Deserialize, Debug)]
 pub struct Xui {
     pub uhs: String,
+    /// The XUID, not included for every relying party.
+    #[serde(default)]
+    pub xid: Option<String>,
+    /// The gamertag, not included for every relying party.
+    #[serde(default)]
+    pub gtg: Option<String>,
+    /// The age group, only included for the Xbox Live relying party.
+    #[serde(default)]
+    pub agg: Option<String>,
+    /// The space separated privileges, only included for the Xbox Live relying party.
+    #[serde(default)]
+    pub prv: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -20,6 +31,13 @@ pub struct DisplayClaims {
     pub xui: Vec<Xui>,
 }
 
+impl DisplayClaims {
+    /// Returns the user hash of the first user in the claims.
+    pub fn uhs(&self) -> Option<&str> {
+        self.xui.first().map(|xui| xui.uhs.as_str())
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct XblOutput {
@@ -29,13 +47,33 @@ pub struct XblOutput {
     pub display_claims: DisplayClaims,
 }
 
-pub fn xbl(token: &str) -> impl AsyncSendSync<Result<XblOutput, XboxError>> {
-    let client = Client::new();
-    let url = format!("https://user.auth.xboxlive.com/user/authenticate");
-    let rps_ticket = format!("d={}", token);
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+/// Where a Microsoft access token was issued, which decides how it is passed to Xbox Live.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TokenKind {
+    /// Issued to an Azure app by the Microsoft identity platform, passed as `d=<token>`.
+    #[default]
+    Azure,
+    /// Issued by `login.live.com`, passed as `t=<token>`.
+    Live,
+}
+
+impl TokenKind {
+    /// Formats an access token of this kind as an RPS ticket.
+    pub(crate) fn rps_ticket(self, token: &str) -> String {
+        match self {
+            Self::Azure => format!("d={token}"),
+            Self::Live => format!("t={token}"),
+        }
+    }
+}
+
+pub fn xbl(
+    config: &AuthConfig,
+    token: &str,
+    kind: TokenKind,
+) -> impl AsyncSendSync<Result<XblOutput, XboxError>> {
+    let url = format!("{}/user/authenticate", config.endpoints.xbox_user);
+    let rps_ticket = kind.rps_ticket(token);
 
     let body = json!({
        "Properties": {
@@ -47,39 +85,13 @@ pub fn xbl(token: &str) -> impl AsyncSendSync<Result<XblOutput, XboxError>> {
        "TokenType": "JWT"
     });
 
-    xbl_internal(client, url, headers, body)
-}
-
-async fn xbl_internal(
-    client: Client,
-    url: String,
-    headers: HeaderMap,
-    body: Value,
-) -> Result<XblOutput, XboxError> {
-    let result = client
+    let request = config
+        .client
         .post(url)
-        .headers(headers)
-        .body(body.to_string())
-        .send()
-        .await;
-
-    let std::result::Result::Ok(response) = result else {
-        println!("Part 1");
-        return Err(XboxError::ResponseError(
-            "Failed to send request".to_string(),
-        ));
-    };
-    let text = response
-        .text()
-        .await
-        .map_err(|_| XboxError::ResponseError("Failed to send request".to_string()))?;
-
-    let std::result::Result::Ok(token) = serde_json::from_str::<XblOutput>(&text) else {
-        return Err(XboxError::ResponseError(
-            "Failed to send request".to_string(),
-        ));
-    };
-    std::result::Result::Ok(token)
+        .header(ACCEPT, "application/json")
+        .json(&body);
+
+    async move { Ok(send_json(request).await?) }
 }
 
 #[derive(Deserialize, Debug)]
@@ -91,71 +103,199 @@ pub struct XtsOutput {
     pub display_claims: DisplayClaims,
 }
 
+/// The service an XSTS token is issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelyingParty {
+    /// Minecraft services, for Java Edition.
+    Java,
+    /// Bedrock Realms.
+    Bedrock,
+    /// Xbox Live itself, whose token carries the user's gamertag.
+    XboxLive,
+}
+
+impl RelyingParty {
+    fn url(self) -> &'static str {
+        match self {
+            Self::Java => "rp://api.minecraftservices.com/",
+            Self::Bedrock => "https://pocket.realms.minecraft.net/",
+            Self::XboxLive => "http://xboxlive.com",
+        }
+    }
+}
+
 pub fn xsts_token(
+    config: &AuthConfig,
     xbl_token: &str,
-    bedrock_rel: bool,
+    party: RelyingParty,
 ) -> impl AsyncSendSync<Result<XtsOutput, XTSError>> {
-    let url = format!("https://xsts.auth.xboxlive.com/xsts/authorize");
-    let bedrock_party = "https://pocket.realms.minecraft.net/";
-    let java_party = "rp://api.minecraftservices.com/";
-    let party = if bedrock_rel == true {
-        bedrock_party
-    } else {
-        java_party
-    };
+    let url = format!("{}/xsts/authorize", config.endpoints.xbox_xsts);
 
-    let client = Client::new();
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-        "Content-Type",
-        header::HeaderValue::from_static("application/json"),
-    );
-    headers.insert(
-        "Accept",
-        header::HeaderValue::from_static("application/json"),
-    );
     let body = json!({
        "Properties": {
            "SandboxId": "RETAIL",
-           "UserTokens": [
-            format!("{}",xbl_token)
-           ]
+           "UserTokens": [xbl_token]
        },
-       "RelyingParty": party,
+       "RelyingParty": party.url(),
        "TokenType": "JWT"
     });
 
-    xsts_internal(client, url, body, headers)
+    let request = config
+        .client
+        .post(url)
+        .header(ACCEPT, "application/json")
+        .json(&body);
+
+    async move {
+        send_json(request).await.map_err(|err| match err {
+            // XSTS explains why it refused the token with an `XErr` code.
+            HttpError::Status { ref body, .. } => match serde_json::from_str::<XErr>(body) {
+                Ok(error) => XTSError::from_code(error.x_err),
+                Err(_) => err.into(),
+            },
+            HttpError::Response(_) => err.into(),
+        })
+    }
 }
 
-async fn xsts_internal(
-    client: Client,
-    url: String,
-    body: Value,
-    headers: HeaderMap,
-) -> Result<XtsOutput, XTSError> {
-    let result = client
-        .post(url)
-        .body(body.to_string())
-        .headers(headers)
-        .send()
-        .await;
-
-    let std::result::Result::Ok(response) = result else {
-        println!("Part  1");
-        return Err(XTSError::ResponseError(
-            "Failed to send request".to_string(),
-        ));
-    };
-    let text = response
-        .text()
-        .await
-        .map_err(|_| XTSError::ResponseError("Failed to read response text".to_string()))?;
-    let std::result::Result::Ok(token) = serde_json::from_str::<XtsOutput>(&text) else {
-        println!("Part  2");
-        return Err(XTSError::ResponseError(
-            "Failed to parse response".to_string(),
-        ));
+/// Who a user is on Xbox Live, as told to the Xbox Live relying party.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct XboxIdentity {
+    /// The user's XUID.
+    pub xuid: String,
+    /// The user's gamertag.
+    pub gamertag: String,
+    /// The user's age group.
+    pub age_group: AgeGroup,
+    /// The Xbox Live privileges the user has, such as `254` for multiplayer.
+    pub privileges: Vec<u32>,
+}
+
+impl XboxIdentity {
+    /// Reads the identity from the display claims of an Xbox Live XSTS token.
+    pub(crate) fn from_xui(xui: Xui) -> Option<Self> {
+        Some(Self {
+            xuid: xui.xid?,
+            gamertag: xui.gtg?,
+            age_group: match xui.agg.as_deref() {
+                Some("Adult") => AgeGroup::Adult,
+                Some("Teen") => AgeGroup::Teen,
+                Some("Child") => AgeGroup::Child,
+                _ => AgeGroup::Unknown,
+            },
+            privileges: xui
+                .prv
+                .unwrap_or_default()
+                .split_whitespace()
+                .filter_map(|privilege| privilege.parse().ok())
+                .collect(),
+        })
+    }
+
+    /// Returns `true` if the user has the given Xbox Live privilege.
+    #[must_use]
+    pub fn has_privilege(&self, privilege: u32) -> bool {
+        self.privileges.contains(&privilege)
+    }
+}
+
+/// The age group of an Xbox Live account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AgeGroup {
+    /// An adult account.
+    Adult,
+    /// A teen account.
+    Teen,
+    /// A child account.
+    Child,
+    /// Xbox Live didn't say, or answered with an age group this crate doesn't know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The result of signing in for Bedrock Edition and Realms.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BedrockAuthData {
+    /// The XSTS token issued for Bedrock Realms.
+    pub xsts_token: String,
+    /// The user hash, `uhs`, the token was issued for.
+    pub user_hash: String,
+    /// The user's XUID, if XSTS included it.
+    pub xuid: Option<String>,
+    /// The user's gamertag, if XSTS included it.
+    pub gamertag: Option<String>,
+    /// When the XSTS token expires, as a Unix timestamp in seconds.
+    pub expires_at: u64,
+    /// The `Authorization` header for the Bedrock Realms API, `XBL3.0 x=<uhs>;<token>`.
+    pub authorization_header: String,
+}
+
+impl BedrockAuthData {
+    pub(crate) fn from_xsts(xts: XtsOutput) -> Result<Self, XTSError> {
+        let xui = xts
+            .display_claims
+            .xui
+            .into_iter()
+            .next()
+            .ok_or_else(|| XTSError::ResponseError("No user in display claims".to_string()))?;
+        let expires_at = timestamp(&xts.not_after).ok_or_else(|| {
+            XTSError::ResponseError(format!("Invalid expiry time {}", xts.not_after))
+        })?;
+
+        Ok(Self {
+            authorization_header: format!("XBL3.0 x={};{}", xui.uhs, xts.token),
+            xsts_token: xts.token,
+            user_hash: xui.uhs,
+            xuid: xui.xid,
+            gamertag: xui.gtg,
+            expires_at,
+        })
+    }
+}
+
+/// The error body XSTS answers with when it refuses to issue a token.
+#[derive(Deserialize, Debug)]
+struct XErr {
+    #[serde(rename = "XErr")]
+    x_err: u64,
+}
+
+/// Converts an Xbox Live `NotAfter` value such as `2024-05-02T20:09:16.6393548Z` into a Unix timestamp.
+pub fn timestamp(not_after: &str) -> Option<u64> {
+    let (date, time) = not_after.trim_end_matches('Z').split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<u64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let time = time.split_once('.').map_or(time, |(time, _)| time);
+    let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    // A second of 60 allows for leap seconds.
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || year < 1970
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Days since the Unix epoch, using a March based year so leap days fall at the end.
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
     };
-    Ok(token)
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    // Only the era depends on the year without bound, so only it can overflow.
+    let days = era
+        .checked_mul(146_097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719_468)?;
+
+    days.checked_mul(86_400)?
+        .checked_add(hour * 3_600 + minute * 60 + second)
 }
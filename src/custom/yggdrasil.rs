@@ -0,0 +1,168 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{
+    mojang::{AuthInfo, Entitlements, MinecraftProfile},
+    send_empty, send_json, HttpError,
+};
+use crate::errors::YggdrasilError;
+
+/// The header an authlib-injector compatible server uses to point at its API root.
+const API_LOCATION: &str = "X-Authlib-Injector-API-Location";
+
+/// The response of `/authserver/authenticate` and `/authserver/refresh`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthResponse {
+    access_token: String,
+    #[serde(default)]
+    selected_profile: Option<MinecraftProfile>,
+}
+
+impl AuthResponse {
+    fn auth_info(self) -> Result<AuthInfo, YggdrasilError> {
+        let profile = self.selected_profile.ok_or(YggdrasilError::NoProfile)?;
+
+        Ok(AuthInfo {
+            access_token: self.access_token,
+            uuid: profile.id.clone(),
+            // Yggdrasil tokens have no fixed lifetime, they stay valid until the server revokes them.
+            expires_in: i32::MAX,
+            profile,
+            xbox_identity: None,
+            entitlements: Entitlements::default(),
+        })
+    }
+}
+
+/// Resolves the API root of a server from the `ALI` header it answers `url` with.
+///
+/// Servers that don't send the header are assumed to serve the API at `url` itself. URLs without a
+/// scheme are treated as HTTPS, as authlib-injector does.
+pub async fn discover(client: &Client, url: &str) -> Result<String, YggdrasilError> {
+    let url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{url}")
+    };
+    let url = Url::parse(&url).map_err(|err| YggdrasilError::ResponseError(err.to_string()))?;
+
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|err| YggdrasilError::ResponseError(err.to_string()))?;
+    let root = match response
+        .headers()
+        .get(API_LOCATION)
+        .and_then(|location| location.to_str().ok())
+    {
+        Some(location) => url
+            .join(location)
+            .map_err(|err| YggdrasilError::ResponseError(err.to_string()))?,
+        None => url,
+    };
+
+    Ok(root.as_str().trim_end_matches('/').to_string())
+}
+
+/// Signs in with a username, or email, and password.
+pub async fn authenticate(
+    client: &Client,
+    root: &str,
+    username: &str,
+    password: &str,
+    client_token: &str,
+) -> Result<AuthInfo, YggdrasilError> {
+    let body = json!({
+        "agent": { "name": "Minecraft", "version": 1 },
+        "username": username,
+        "password": password,
+        "clientToken": client_token,
+        "requestUser": false,
+    });
+    let response: AuthResponse = send_json(
+        client
+            .post(format!("{root}/authserver/authenticate"))
+            .json(&body),
+    )
+    .await?;
+
+    response.auth_info()
+}
+
+/// Exchanges an access token for a new one, invalidating the old token.
+pub async fn refresh(
+    client: &Client,
+    root: &str,
+    access_token: &str,
+    client_token: &str,
+) -> Result<AuthInfo, YggdrasilError> {
+    let response: AuthResponse = send_json(
+        client
+            .post(format!("{root}/authserver/refresh"))
+            .json(&tokens(access_token, client_token)),
+    )
+    .await?;
+
+    response.auth_info()
+}
+
+/// Checks whether an access token can still be used, the server answers `403 Forbidden` if not.
+pub async fn validate(
+    client: &Client,
+    root: &str,
+    access_token: &str,
+    client_token: &str,
+) -> Result<bool, YggdrasilError> {
+    let request = client
+        .post(format!("{root}/authserver/validate"))
+        .json(&tokens(access_token, client_token));
+
+    match send_empty(request).await {
+        Ok(()) => Ok(true),
+        Err(HttpError::Status { status: 403, .. }) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Revokes an access token.
+pub async fn invalidate(
+    client: &Client,
+    root: &str,
+    access_token: &str,
+    client_token: &str,
+) -> Result<(), YggdrasilError> {
+    Ok(send_empty(
+        client
+            .post(format!("{root}/authserver/invalidate"))
+            .json(&tokens(access_token, client_token)),
+    )
+    .await?)
+}
+
+/// Revokes every access token of an account.
+pub async fn signout(
+    client: &Client,
+    root: &str,
+    username: &str,
+    password: &str,
+) -> Result<(), YggdrasilError> {
+    let body = json!({ "username": username, "password": password });
+
+    Ok(send_empty(
+        client
+            .post(format!("{root}/authserver/signout"))
+            .json(&body),
+    )
+    .await?)
+}
+
+fn tokens(access_token: &str, client_token: &str) -> Value {
+    json!({ "accessToken": access_token, "clientToken": client_token })
+}
@@ -8,6 +8,13 @@ use thiserror::Error;
 pub enum TokenError {
     /// Response Failed: {0}
     ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
 }
 
 /// The `XboxError` enum represents potential errors that can occur during Xbox-related operations.
@@ -15,6 +22,15 @@ pub enum TokenError {
 pub enum XboxError {
     /// Response Failed: {0}
     ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+    /// Signing the request failed: {0}
+    Signing(String),
 }
 
 /// The `XTSError` enum represents potential errors that can occur during XTS-related operations.
@@ -22,6 +38,60 @@ pub enum XboxError {
 pub enum XTSError {
     /// Response Failed: {0}
     ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+    /// This Microsoft account has no Xbox profile, sign in at xbox.com to create one.
+    NoXboxProfile,
+    /// Xbox Live is not available in this account's country or region.
+    RegionUnavailable,
+    /// This account needs adult verification on the Xbox website before it can sign in.
+    AdultVerification,
+    /// This is a child account and must be added to a Microsoft family by an adult before it can sign in.
+    ChildAccount,
+    /// Xbox Live refused the sign in with error code {0}.
+    Refused(u64),
+}
+
+impl XTSError {
+    /// Maps an `XErr` code returned by XSTS to its error.
+    #[must_use]
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            2_148_916_233 => Self::NoXboxProfile,
+            2_148_916_235 => Self::RegionUnavailable,
+            2_148_916_236 | 2_148_916_237 => Self::AdultVerification,
+            2_148_916_238 => Self::ChildAccount,
+            code => Self::Refused(code),
+        }
+    }
+}
+
+/// The `MojangError` enum represents potential errors that can occur during Minecraft services operations.
+#[derive(Display, Error, Debug)]
+pub enum MojangError {
+    /// Response Failed: {0}
+    ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+    /// The account has no Minecraft profile.
+    NoProfile,
+    /// The account does not own Minecraft: Java Edition.
+    NotOwned {
+        /// The Minecraft access token, which is still valid for launching the game in demo mode.
+        access_token: String,
+        /// How long the access token is valid for, in seconds.
+        expires_in: i32,
+    },
 }
 
 /// The `OAuthError` enum represents potential errors that can occur during OAuth authentication.
@@ -33,6 +103,105 @@ pub enum OAuthError {
     ParseError(String),
     /// Binding error: {0}
     BindError(String),
+    /// The callback state was missing or did not match the sign in request.
+    StateMismatch,
+    /// No sign in callback was received before the timeout.
+    Timeout,
+}
+
+/// The `DeviceCodeError` enum represents potential errors that can occur while polling for a device code sign in.
+#[derive(Display, Error, Debug)]
+pub enum DeviceCodeError {
+    /// The device code expired before the user finished signing in.
+    Expired,
+    /// The user declined the sign in request.
+    Declined,
+    /// The device code was not recognised: {0}
+    BadVerificationCode(String),
+    /// Response Failed: {0}
+    ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+}
+
+/// The stage of the sign in an [`AuthError`] happened in.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthStage {
+    /// Microsoft sign in
+    OAuth,
+    /// Device code sign in
+    DeviceCode,
+    /// Microsoft token exchange
+    Token,
+    /// Xbox Live authentication
+    Xbox,
+    /// XSTS authorization
+    Xsts,
+    /// Minecraft services
+    Mojang,
+}
+
+/// The `AuthError` enum represents any error that can occur while signing in, from Microsoft through to Minecraft services.
+///
+/// Each variant wraps the error of the stage that failed, so callers can match on the stage, or use
+/// [`AuthError::status`] and [`AuthError::body`] to inspect the HTTP response that caused it.
+#[derive(Display, Error, Debug)]
+pub enum AuthError {
+    /// Microsoft sign in failed: {0}
+    OAuth(#[from] OAuthError),
+    /// Device code sign in failed: {0}
+    DeviceCode(#[from] DeviceCodeError),
+    /// Microsoft token exchange failed: {0}
+    Token(#[from] TokenError),
+    /// Xbox Live authentication failed: {0}
+    Xbox(#[from] XboxError),
+    /// XSTS authorization failed: {0}
+    Xsts(#[from] XTSError),
+    /// Minecraft services failed: {0}
+    Mojang(#[from] MojangError),
+}
+
+impl AuthError {
+    /// Returns the stage of the sign in that failed.
+    #[must_use]
+    pub fn stage(&self) -> AuthStage {
+        match self {
+            Self::OAuth(_) => AuthStage::OAuth,
+            Self::DeviceCode(_) => AuthStage::DeviceCode,
+            Self::Token(_) => AuthStage::Token,
+            Self::Xbox(_) => AuthStage::Xbox,
+            Self::Xsts(_) => AuthStage::Xsts,
+            Self::Mojang(_) => AuthStage::Mojang,
+        }
+    }
+
+    /// Returns the HTTP status of the failed response, if the server answered with an error status.
+    #[must_use]
+    pub fn status(&self) -> Option<u16> {
+        self.response().map(|(status, _)| status)
+    }
+
+    /// Returns the raw body of the failed response, if the server answered with an error status.
+    #[must_use]
+    pub fn body(&self) -> Option<&str> {
+        self.response().map(|(_, body)| body)
+    }
+
+    fn response(&self) -> Option<(u16, &str)> {
+        match self {
+            Self::DeviceCode(DeviceCodeError::Status { status, body })
+            | Self::Token(TokenError::Status { status, body })
+            | Self::Xbox(XboxError::Status { status, body })
+            | Self::Xsts(XTSError::Status { status, body })
+            | Self::Mojang(MojangError::Status { status, body }) => Some((*status, body)),
+            _ => None,
+        }
+    }
 }
 
 /// The `LaunchError` enum represents potential errors that can occur during Launching minecraft.
@@ -41,3 +210,70 @@ pub enum LaunchError {
     /// Launch Requirements Failed: {0}
     Requirements(String),
 }
+
+/// The `StoreError` enum represents potential errors that can occur while reading or writing an account store.
+#[derive(Display, Error, Debug)]
+pub enum StoreError {
+    /// Reading or writing the store failed: {0}
+    Io(String),
+    /// The key is invalid: {0}
+    Key(String),
+    /// The store could not be decrypted, either the key is wrong or the file was modified.
+    Decrypt,
+    /// The store is malformed: {0}
+    Format(String),
+    /// The store was written in format version {0}, which this version can't read.
+    UnsupportedVersion(u32),
+}
+
+/// The `AccountError` enum represents potential errors that can occur while managing saved accounts.
+#[derive(Display, Error, Debug)]
+pub enum AccountError {
+    /// No account is active.
+    NoActiveAccount,
+    /// No saved account has the UUID {0}.
+    UnknownAccount(String),
+    /// Signing in failed: {0}
+    Auth(#[from] AuthError),
+    /// The account store failed: {0}
+    Store(#[from] StoreError),
+}
+
+/// The `OfflineError` enum represents potential errors that can occur while creating an offline account.
+#[derive(Display, Error, Debug, PartialEq, Eq)]
+pub enum OfflineError {
+    /// Usernames must be 3 to 16 characters long, not {0}.
+    InvalidLength(usize),
+    /// Usernames may only contain letters, digits and underscores, not `{0}`.
+    InvalidCharacter(char),
+}
+
+/// The `YggdrasilError` enum represents potential errors that can occur while signing in to a Yggdrasil auth server.
+#[derive(Display, Error, Debug)]
+pub enum YggdrasilError {
+    /// Response Failed: {0}
+    ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body, a JSON object whose `errorMessage` explains the failure.
+        body: String,
+    },
+    /// The account has no profile selected.
+    NoProfile,
+}
+
+/// The `SessionError` enum represents potential errors that can occur while joining or verifying a server through the session server.
+#[derive(Display, Error, Debug)]
+pub enum SessionError {
+    /// Response Failed: {0}
+    ResponseError(String),
+    /// Request failed with status {status}: {body}
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+}
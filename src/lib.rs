@@ -15,11 +15,58 @@ mod tests;
 #[cfg(feature = "custom-auth")]
 mod custom;
 
+#[cfg(feature = "minecraft-auth")]
+mod minecraft;
+
+/// A local stand-in for the sign in services, for testing without a network or a registered app.
+///
+/// Enabled by the `mock` feature.
+#[cfg(all(feature = "custom-auth", any(test, feature = "mock")))]
+pub mod mock;
+
 #[cfg(feature = "custom-auth")]
 pub use custom::mojang::AuthInfo as CustomAuthData;
 
 #[cfg(feature = "custom-auth")]
-use custom::{code, mojang, oauth, xbox};
+pub use custom::session::AuthSession;
+
+#[cfg(feature = "minecraft-auth")]
+pub use minecraft::store::{AccountStore, StoreKey};
+
+#[cfg(feature = "minecraft-auth")]
+pub use minecraft::accounts::{Account, AccountManager};
+
+#[cfg(feature = "minecraft-auth")]
+pub use minecraft::live::LiveLogin;
+
+#[cfg(feature = "minecraft-auth")]
+pub use minecraft::session_server::{GameProfile, ProfileProperty, SessionServer};
+
+#[cfg(feature = "minecraft-auth")]
+pub use minecraft::sisu::{DeviceToken, ProofKey, Sisu};
+
+#[cfg(feature = "custom-auth")]
+pub use custom::config::{AuthConfig, Endpoints};
+
+#[cfg(feature = "custom-auth")]
+pub use custom::mojang::{
+    Cape, Entitlement, EntitlementKind, Entitlements, MinecraftProfile, Skin,
+};
+
+#[cfg(feature = "custom-auth")]
+pub use custom::oauth::{CallbackPage, Prompt, RedirectPort};
+
+#[cfg(feature = "custom-auth")]
+pub use custom::xbox::{AgeGroup, BedrockAuthData, TokenKind, XboxIdentity};
+
+#[cfg(feature = "custom-auth")]
+use custom::{code, mojang, oauth, offline, session, xbox, yggdrasil};
+
+#[cfg(feature = "custom-auth")]
+use errors::AuthError;
+
+#[cfg(feature = "custom-auth")]
+use std::time::Duration;
 
 #[cfg(feature = "custom-launch")]
 use std::{
@@ -41,7 +88,19 @@ pub(crate) const EXPERIMENTAL_MESSAGE: &str =
 pub struct Oauth {
     url: String,
     port: u16,
-    client_id: String,
+    listener: Option<std::net::TcpListener>,
+    params: oauth::Params,
+    prompt: Option<Prompt>,
+    login_hint: Option<String>,
+    domain_hint: Option<String>,
+    redirect_path: String,
+    code_verifier: String,
+    code_challenge: String,
+    state: String,
+    success_page: CallbackPage,
+    failure_page: CallbackPage,
+    timeout: Option<Duration>,
+    config: AuthConfig,
 }
 
 #[cfg(feature = "custom-auth")]
@@ -49,7 +108,9 @@ impl Oauth {
     /// Initializes a new `Oauth` instance.
     ///
     /// This method sets up the OAuth authentication process by constructing the authorization URL
-    /// and storing the client ID and port for later use.
+    /// and storing the client ID and port for later use. A PKCE code verifier is generated for every
+    /// instance, so public clients without a client secret can complete the sign in, along with a
+    /// random `state` that the callback must echo back before its code is accepted.
     ///
     /// # Arguments
     ///
@@ -59,18 +120,211 @@ impl Oauth {
     /// # Returns
     ///
     /// * `Self` - A new instance of `Oauth` configured with the provided client ID and port.
+    #[must_use]
     pub fn new(client_id: &str, port: Option<u16>) -> Self {
-        let port = port.unwrap_or(8000);
-        let params = format!("client_id={}&response_type=code&redirect_uri=http://localhost:{}&response_mode=query&scope={}&state=12345", client_id, port, SCOPE);
-        let url = format!(
-            "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize/?{}",
-            params
-        );
+        let builder = Self::builder(client_id);
+        match port {
+            Some(port) => builder.port(port),
+            None => builder,
+        }
+        .build()
+    }
 
-        Self {
-            url,
+    /// Starts building an `Oauth` instance with more control over the sign in.
+    ///
+    /// See [`OauthBuilder`] for the tenant, scopes, prompt, hints and redirect path it can set.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The client ID obtained from the Minecraft authentication service.
+    ///
+    /// # Returns
+    ///
+    /// * `OauthBuilder` - A builder using the same defaults as [`Oauth::new`].
+    #[must_use]
+    pub fn builder(client_id: &str) -> OauthBuilder {
+        OauthBuilder::new(client_id)
+    }
+
+    /// Initializes a new `Oauth` instance with the local server already listening.
+    ///
+    /// Unlike [`Oauth::new`], the port is bound straight away, so a busy port is reported here
+    /// instead of when launching, and the authorization URL always uses the port actually bound.
+    /// Azure ignores the port of `http://localhost` redirect URIs, so any port works with the
+    /// redirect URI registered for the app.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The client ID obtained from the Minecraft authentication service.
+    /// * `port` - Which port, or ports, the local server may listen on.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, errors::OAuthError>` - A new instance of `Oauth`, or an error if no port could be bound.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::OAuthError::BindError`] if none of the requested ports are free.
+    pub fn bind(client_id: &str, port: RedirectPort) -> Result<Self, errors::OAuthError> {
+        Self::builder(client_id).bind(port)
+    }
+
+    fn with_listener(
+        builder: OauthBuilder,
+        port: u16,
+        listener: Option<std::net::TcpListener>,
+    ) -> Self {
+        let (code_verifier, code_challenge) = oauth::pkce();
+        let mut oauth = Self {
+            url: String::new(),
             port,
-            client_id: client_id.to_string(),
+            listener,
+            params: builder.params,
+            prompt: builder.prompt,
+            login_hint: builder.login_hint,
+            domain_hint: builder.domain_hint,
+            redirect_path: builder.redirect_path,
+            code_verifier,
+            code_challenge,
+            state: oauth::state(),
+            success_page: CallbackPage::default_success(),
+            failure_page: CallbackPage::default_failure(),
+            timeout: None,
+            config: AuthConfig::default(),
+        };
+        oauth.url = oauth.authorize_url();
+        oauth
+    }
+
+    fn authorize_url(&self) -> String {
+        let Self {
+            params: oauth::Params {
+                client_id, tenant, ..
+            },
+            state,
+            code_challenge,
+            ..
+        } = self;
+        let redirect_uri = self.redirect_uri();
+        let scope = self.params.scope_query();
+        let mut params = format!("client_id={client_id}&response_type=code&redirect_uri={redirect_uri}&response_mode=query&scope={scope}&state={state}&code_challenge={code_challenge}&code_challenge_method=S256");
+        if let Some(prompt) = self.prompt {
+            params.push_str("&prompt=");
+            params.push_str(prompt.as_str());
+        }
+        if let Some(login_hint) = &self.login_hint {
+            params.push_str("&login_hint=");
+            params.push_str(&oauth::encode(login_hint));
+        }
+        if let Some(domain_hint) = &self.domain_hint {
+            params.push_str("&domain_hint=");
+            params.push_str(&oauth::encode(domain_hint));
+        }
+
+        format!(
+            "{}/{tenant}/oauth2/v2.0/authorize/?{params}",
+            self.config.endpoints.microsoft
+        )
+    }
+
+    /// Retrieves the redirect URI the authorization URL sends the user back to.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The `http://localhost` redirect URI, which must be registered for the app.
+    #[must_use]
+    pub fn redirect_uri(&self) -> String {
+        format!("http://localhost:{}{}", self.port, self.redirect_path)
+    }
+
+    /// Sets the HTTP client and endpoints used to sign in.
+    ///
+    /// The authorization URL is rebuilt to use the configured Microsoft endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The client and endpoints to use.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The `Oauth` instance using the given config.
+    #[must_use]
+    pub fn config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self.url = self.authorize_url();
+        self
+    }
+
+    /// Sets how long to wait for the user to finish signing in.
+    ///
+    /// Without a timeout the local server waits until the callback arrives. Dropping the future
+    /// returned by [`Oauth::launch`] also cancels the sign in and closes the local server.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The longest time to wait for the sign in callback.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The `Oauth` instance using the given timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the page the browser is shown after a successful sign in.
+    ///
+    /// By default a short page asking the user to return to the launcher is shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - Either an HTML document or a URL to redirect the browser to.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The `Oauth` instance using the given page.
+    #[must_use]
+    pub fn success_page(mut self, page: CallbackPage) -> Self {
+        self.success_page = page;
+        self
+    }
+
+    /// Sets the page the browser is shown after a failed sign in.
+    ///
+    /// By default a short page asking the user to try again is shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - Either an HTML document or a URL to redirect the browser to.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The `Oauth` instance using the given page.
+    #[must_use]
+    pub fn failure_page(mut self, page: CallbackPage) -> Self {
+        self.failure_page = page;
+        self
+    }
+
+    /// Retrieves the port of the local server.
+    ///
+    /// # Returns
+    ///
+    /// * `u16` - The port the authorization URL redirects to.
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns a handle to the bound listener, or the port to bind when launching.
+    fn listener(&self) -> Result<oauth::Listener, errors::OAuthError> {
+        match &self.listener {
+            Some(listener) => listener
+                .try_clone()
+                .map(oauth::Listener::Bound)
+                .map_err(|err| errors::OAuthError::BindError(err.to_string())),
+            None => Ok(oauth::Listener::Port(self.port)),
         }
     }
 
@@ -89,96 +343,274 @@ impl Oauth {
     ///
     /// This method completes the OAuth authentication process by launching a local server to
     /// receive the authorization code, exchanging it for an access token, and then using this token
-    /// to launch Minecraft. The method supports both Bedrock Edition and Java Edition of Minecraft.
+    /// to launch Minecraft: Java Edition. Use [`Oauth::launch_bedrock`] for Bedrock Edition.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CustomAuthData, AuthError>` - A result containing the authentication data or an error if the process fails.
+    ///   A [`errors::MojangError::NotOwned`] is returned if the account doesn't own the game, carrying
+    ///   the Minecraft access token so the game can still be launched in demo mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] for the stage that failed, if the user doesn't sign in or any authentication step fails.
+    pub async fn launch(&self, client_secret: Option<&str>) -> Result<CustomAuthData, AuthError> {
+        let token = self.sign_in(client_secret).await?;
+
+        minecraft_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await
+    }
+
+    /// Launches the OAuth authentication process for Bedrock Edition and Realms.
+    ///
+    /// This works like [`Oauth::launch`], but authorizes the user for Bedrock Realms instead of
+    /// Minecraft services.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BedrockAuthData, AuthError>` - A result containing the XSTS token for Bedrock Realms or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] for the stage that failed, if the user doesn't sign in or any authentication step fails.
+    pub async fn launch_bedrock(
+        &self,
+        client_secret: Option<&str>,
+    ) -> Result<BedrockAuthData, AuthError> {
+        let token = self.sign_in(client_secret).await?;
+
+        bedrock_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await
+    }
+
+    /// Completes the sign in from a redirect captured outside of the local server.
+    ///
+    /// Launchers that show the sign in in their own web view, or register a custom URI scheme, can
+    /// catch the redirect to [`Oauth::redirect_uri`] themselves and pass it here instead of
+    /// calling [`Oauth::launch`], so no local server is started. The redirect is checked the same way
+    /// the local server checks it before its code is redeemed.
     ///
     /// # Arguments
     ///
-    /// * `bedrock_relm` - A boolean indicating whether to launch the Bedrock Edition of Minecraft.
-    /// * `client_secret` - The client secret obtained from the Minecraft authentication service.
+    /// * `redirect` - The full redirect URL, or just its query string.
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
     ///
     /// # Returns
     ///
-    /// * `Result<CustomAuthData, Box<dyn std::error::Error>>` - A result containing the authentication data or an error if the process fails.
-    pub async fn launch(
+    /// * `Result<CustomAuthData, AuthError>` - A result containing the authentication data or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::OAuthError::StateMismatch`] if the redirect's `state` doesn't match this sign in,
+    /// [`errors::OAuthError::AuthenticationFailure`] if it carries an `error`, or an [`AuthError`] for
+    /// whichever later stage failed.
+    pub async fn launch_from_redirect(
+        &self,
+        redirect: &str,
+        client_secret: Option<&str>,
+    ) -> Result<CustomAuthData, AuthError> {
+        let query = oauth::redirect_query(redirect);
+        let info = oauth::check_info(oauth::parse_info(query)?, &self.state)?;
+        let token = self.redeem(info, client_secret).await?;
+
+        minecraft_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await
+    }
+
+    /// Waits for the user to sign in and redeems the code the callback delivers.
+    async fn sign_in(&self, client_secret: Option<&str>) -> Result<oauth::Token, AuthError> {
+        let http_server = oauth::server(
+            self.listener()?,
+            self.state.clone(),
+            match self.redirect_path.as_str() {
+                "" => "/".to_string(),
+                path => path.to_string(),
+            },
+            self.success_page.clone(),
+            self.failure_page.clone(),
+            self.timeout,
+        )
+        .await?;
+
+        self.redeem(http_server, client_secret).await
+    }
+
+    /// Redeems the code of a checked sign in callback.
+    async fn redeem(
         &self,
-        bedrock_relm: bool,
-        client_secret: &str,
-    ) -> Result<CustomAuthData, Box<dyn std::error::Error>> {
-        let http_server = oauth::server(self.port)?.await?;
+        info: oauth::Info,
+        client_secret: Option<&str>,
+    ) -> Result<oauth::Token, AuthError> {
         let token = oauth::token(
-            http_server
+            &self.config,
+            &info
                 .code
-                .expect("\x1b[31mXbox Expected code.\x1b[0m")
-                .as_str(),
-            &self.client_id,
-            self.port,
+                .ok_or_else(|| errors::OAuthError::ParseError("No code received".to_string()))?,
+            &self.params,
+            &self.redirect_uri(),
             client_secret,
+            &self.code_verifier,
         )
         .await?;
-        let xbox = xbox::xbl(&token.access_token).await?;
-        let xts = xbox::xsts_token(&xbox.token, bedrock_relm).await?;
-
-        if bedrock_relm {
-            Ok(CustomAuthData {
-                access_token: "null".to_string(),
-                uuid: "null".to_string(),
-                expires_in: 0,
-                xts_token: Some(xts.token),
-            })
-        } else {
-            Ok(mojang::token(&xbox.display_claims.xui[0].uhs, &xts.token).await?)
-        }
+
+        Ok(token)
+    }
+
+    /// Launches the OAuth authentication process and returns a persistable session.
+    ///
+    /// This works like [`Oauth::launch`] for Java Edition, but keeps the Microsoft refresh token and
+    /// records absolute expiry times, so the session can be saved and resumed with [`Oauth::refresh_session`].
+    ///
+    /// # Arguments
+    ///
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuthSession, AuthError>` - A result containing the session or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user doesn't sign in or any authentication step fails.
+    pub async fn launch_session(
+        &self,
+        client_secret: Option<&str>,
+    ) -> Result<AuthSession, AuthError> {
+        let token = self.sign_in(client_secret).await?;
+        let session = java_session(
+            &self.config,
+            &token.access_token,
+            xbox::TokenKind::Azure,
+            token.refresh_token,
+            token.expires_in.into(),
+        )
+        .await?;
+
+        Ok(session.with_params(&self.params))
     }
 
     /// Refreshes the OAuth authentication process.
     ///
-    /// This method is used to refresh the access token using the refresh token.
+    /// This method redeems a Microsoft refresh token for a new access token and then re-runs the
+    /// Xbox Live and Minecraft authentication, so the user doesn't have to sign in again.
     ///
     /// # Arguments
     ///
-    /// * `refresh_token` - The refresh token obtained from the Minecraft authentication service.
-    /// * `client_id` - The client ID obtained from the Minecraft authentication service.
-    /// * `port` - An optional port number for the local server. Defaults to 8000 if not provided.
-    /// * `client_secret` - The client secret obtained from the Minecraft authentication service.
+    /// * `refresh_token` - The refresh token obtained from a previous sign in.
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
     ///
     /// # Returns
     ///
-    /// * `Result<CustomAuthData, Box<dyn std::error::Error>>` - A result containing the refreshed authentication data or an error if the process fails.
-    #[cfg(feature = "refresh")]
+    /// * `Result<(CustomAuthData, String), AuthError>` - A result containing the refreshed authentication data
+    ///   and the rotated refresh token, which replaces the one passed in, or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh token was rejected or any later authentication step fails.
     pub async fn refresh(
         &self,
         refresh_token: &str,
-        client_id: &str,
-        port: Option<u16>,
-        client_secret: &str,
-    ) -> Result<CustomAuthData, Box<dyn std::error::Error>> {
-        let port = port.unwrap_or(8000);
-        let token = oauth::token(refresh_token, client_id, port, client_secret).await?;
-        Ok(token)
+        client_secret: Option<&str>,
+    ) -> Result<(CustomAuthData, String), AuthError> {
+        let token =
+            oauth::refresh(&self.config, refresh_token, &self.params, client_secret).await?;
+        let auth =
+            minecraft_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await?;
+
+        Ok((auth, token.refresh_token))
+    }
+
+    /// Refreshes the OAuth authentication process for Bedrock Edition and Realms.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh_token` - The refresh token obtained from a previous sign in.
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(BedrockAuthData, String), AuthError>` - A result containing the refreshed Bedrock authentication data
+    ///   and the rotated refresh token, which replaces the one passed in, or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh token was rejected or any later authentication step fails.
+    pub async fn refresh_bedrock(
+        &self,
+        refresh_token: &str,
+        client_secret: Option<&str>,
+    ) -> Result<(BedrockAuthData, String), AuthError> {
+        let token =
+            oauth::refresh(&self.config, refresh_token, &self.params, client_secret).await?;
+        let auth = bedrock_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await?;
+
+        Ok((auth, token.refresh_token))
+    }
+
+    /// Refreshes a saved session.
+    ///
+    /// This method redeems the session's refresh token and re-runs the Xbox Live and Minecraft
+    /// authentication, returning a new session with a rotated refresh token and fresh expiry times.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session to refresh.
+    /// * `client_secret` - The client secret obtained from the Minecraft authentication service, or `None` for public clients.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuthSession, AuthError>` - A result containing the refreshed session or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh token was rejected or any later authentication step fails.
+    pub async fn refresh_session(
+        &self,
+        session: &AuthSession,
+        client_secret: Option<&str>,
+    ) -> Result<AuthSession, AuthError> {
+        let token = oauth::refresh(
+            &self.config,
+            &session.refresh_token,
+            &self.params,
+            client_secret,
+        )
+        .await?;
+        let session = java_session(
+            &self.config,
+            &token.access_token,
+            xbox::TokenKind::Azure,
+            token.refresh_token,
+            token.expires_in.into(),
+        )
+        .await?;
+
+        Ok(session.with_params(&self.params))
     }
 }
 
-/// Device Code Authentication
+/// Builds an [`Oauth`] sign in with a tenant, extra scopes, a prompt or hints.
 ///
-/// This struct represents the device code authentication process for Minecraft, specifically designed for use with custom Azure applications.
-/// It is used to authenticate a device and obtain a token that can be used to launch Minecraft.
+/// Every setting defaults to what [`Oauth::new`] uses, so only what differs needs to be set.
 #[cfg(feature = "custom-auth")]
-pub struct DeviceCode {
-    url: String,
-    message: String,
-    expires_in: u32,
-    user_code: String,
-    device_code: String,
-    client_id: String,
+#[derive(Debug, Clone)]
+pub struct OauthBuilder {
+    params: oauth::Params,
+    port: Option<u16>,
+    prompt: Option<Prompt>,
+    login_hint: Option<String>,
+    domain_hint: Option<String>,
+    redirect_path: String,
 }
 
 #[cfg(feature = "custom-auth")]
-impl DeviceCode {
-    /// Initializes a new `DeviceCode` instance.
-    ///
-    /// This method starts the device code authentication process by making an asynchronous request
-    /// to the authentication server. It returns a future that resolves to a `Result` containing the
-    /// `DeviceCode` instance on success or an error if the request fails.
+impl OauthBuilder {
+    /// Initializes a new `OauthBuilder` for personal Microsoft accounts on port 8000.
     ///
     /// # Arguments
     ///
@@ -186,81 +618,812 @@ impl DeviceCode {
     ///
     /// # Returns
     ///
-    /// * `impl async_trait_alias::AsyncSendSync<Result<Self, reqwest::Error>>` - A future that resolves to a `Result` containing the `DeviceCode` instance or an error.
-    pub fn new(
-        client_id: &str,
-    ) -> impl async_trait_alias::AsyncSendSync<Result<Self, reqwest::Error>> {
-        println!("{}", EXPERIMENTAL_MESSAGE);
-        let client_id_str = client_id.to_string();
-        async move {
-            let response_data = code::device_authentication_code(&client_id_str).await?;
-
-            Ok(Self {
-                url: response_data.verification_uri,
-                message: response_data.message,
-                expires_in: response_data.expires_in,
-                user_code: response_data.user_code,
-                device_code: response_data.device_code,
-                client_id: client_id_str,
-            })
+    /// * `Self` - A new builder.
+    #[must_use]
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            params: oauth::Params::new(client_id),
+            port: None,
+            prompt: None,
+            login_hint: None,
+            domain_hint: None,
+            redirect_path: String::new(),
         }
     }
 
-    /// Provides pre-launch information.
+    /// Sets the port of the local server, used by [`OauthBuilder::build`].
     ///
-    /// This method returns a tuple containing the verification URL, the message to display to the user,
-    /// the expiration time of the device code, and the user code. This information is useful for guiding
-    /// the user through the device code authentication process.
+    /// # Arguments
+    ///
+    /// * `port` - The port the authorization URL redirects to.
     ///
     /// # Returns
     ///
-    /// * `(&str, &str, u32, &str)` - A tuple containing the verification URL, the message, the expiration time, and the user code.
-    pub fn preinfo(&self) -> (&str, &str, u32, &str) {
-        (&self.url, &self.message, self.expires_in, &self.user_code)
+    /// * `Self` - The builder using the given port.
+    #[must_use]
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
     }
 
-    /// Launches Minecraft using the device code authentication process.
+    /// Sets the tenant users sign in to.
     ///
-    /// This method completes the device code authentication process by authenticating the device
-    /// and obtaining a token. It then uses this token to launch Minecraft. The method supports both
-    /// Bedrock Edition and Java Edition of Minecraft.
+    /// Xbox Live only accepts personal Microsoft accounts, so apps registered for them should keep
+    /// the default, `consumers`. Apps registered for work and school accounts too need `common`.
     ///
     /// # Arguments
     ///
-    /// * `bedrock_relm` - A boolean indicating whether to launch the Bedrock Edition of Minecraft.
+    /// * `tenant` - `consumers`, `common`, `organizations` or a tenant ID.
     ///
     /// # Returns
     ///
-    /// * `Result<CustomAuthData, Box<dyn std::error::Error>>` - A result containing the authentication data or an error if the process fails.
-    pub async fn launch(
-        &self,
-        bedrock_relm: bool,
-    ) -> Result<CustomAuthData, Box<dyn std::error::Error>> {
-        let token = code::authenticate_device(&self.device_code, &self.client_id).await?;
-        let xbox = xbox::xbl(&token.token).await?;
-        let xts = xbox::xsts_token(&xbox.token, bedrock_relm).await?;
-        if bedrock_relm {
-            Ok(CustomAuthData {
-                access_token: "null".to_string(),
-                uuid: "null".to_string(),
-                expires_in: 0,
-                xts_token: Some(xts.token),
-            })
-        } else {
-            Ok(mojang::token(&xbox.display_claims.xui[0].uhs, &xts.token).await?)
-        }
+    /// * `Self` - The builder using the given tenant.
+    #[must_use]
+    pub fn tenant(mut self, tenant: &str) -> Self {
+        self.params.tenant = tenant.to_string();
+        self
     }
 
-    /// Refreshes the device code authentication process.
+    /// Requests a scope on top of the Xbox Live ones, such as `openid`.
+    ///
+    /// Can be called once for every extra scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - The scope to add.
+    ///
+    /// # Returns
     ///
-    /// This method is marked as experimental and currently does not perform any actions.
+    /// * `Self` - The builder requesting the given scope too.
+    #[must_use]
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.params.scope.push(' ');
+        self.params.scope.push_str(scope);
+        self
+    }
+
+    /// Sets the prompt shown when the user opens the authorization URL.
+    ///
+    /// [`Prompt::SelectAccount`] lets launchers with several accounts add one other than the one
+    /// the browser is signed in to.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt to show.
     ///
-    /// # Note
+    /// # Returns
     ///
-    /// This method is intended for future use when implementing refresh functionality for the device code authentication process.
-    pub async fn refresh(&self) {
-        println!("{}", EXPERIMENTAL_MESSAGE);
+    /// * `Self` - The builder using the given prompt.
+    #[must_use]
+    pub fn prompt(mut self, prompt: Prompt) -> Self {
+        self.prompt = Some(prompt);
+        self
     }
+
+    /// Fills in the username on the sign in page, for example when refreshing a known account failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `login_hint` - The email address or phone number of the account.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder using the given hint.
+    #[must_use]
+    pub fn login_hint(mut self, login_hint: &str) -> Self {
+        self.login_hint = Some(login_hint.to_string());
+        self
+    }
+
+    /// Skips the account type picker by saying which kind of account the user has.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain_hint` - `consumers` for personal accounts, or the domain of a work or school tenant.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder using the given hint.
+    #[must_use]
+    pub fn domain_hint(mut self, domain_hint: &str) -> Self {
+        self.domain_hint = Some(domain_hint.to_string());
+        self
+    }
+
+    /// Sets the path of the redirect URI, for apps registered with `http://localhost/callback`
+    /// rather than `http://localhost`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path, such as `/callback`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The builder using the given path.
+    #[must_use]
+    pub fn redirect_path(mut self, path: &str) -> Self {
+        self.redirect_path = match path {
+            "" | "/" => String::new(),
+            path if path.starts_with('/') => path.to_string(),
+            path => format!("/{path}"),
+        };
+        self
+    }
+
+    /// Builds the `Oauth` instance, binding the port only when launching, like [`Oauth::new`].
+    ///
+    /// # Returns
+    ///
+    /// * `Oauth` - The configured instance.
+    #[must_use]
+    pub fn build(self) -> Oauth {
+        let port = self.port.unwrap_or(8000);
+        Oauth::with_listener(self, port, None)
+    }
+
+    /// Builds the `Oauth` instance with the local server already listening, like [`Oauth::bind`].
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - Which port, or ports, the local server may listen on.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Oauth, errors::OAuthError>` - The configured instance, or an error if no port could be bound.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::OAuthError::BindError`] if none of the requested ports are free.
+    pub fn bind(self, port: RedirectPort) -> Result<Oauth, errors::OAuthError> {
+        let listener = port.bind()?;
+        let port = listener
+            .local_addr()
+            .map_err(|err| errors::OAuthError::BindError(err.to_string()))?
+            .port();
+
+        Ok(Oauth::with_listener(self, port, Some(listener)))
+    }
+}
+
+/// Device Code Authentication
+///
+/// This struct represents the device code authentication process for Minecraft, specifically designed for use with custom Azure applications.
+/// It is used to authenticate a device and obtain a token that can be used to launch Minecraft.
+#[cfg(feature = "custom-auth")]
+pub struct DeviceCode {
+    url: String,
+    message: String,
+    expires_in: u32,
+    expires_at: std::time::Instant,
+    interval: u16,
+    user_code: String,
+    device_code: String,
+    client_id: String,
+    config: AuthConfig,
+}
+
+#[cfg(feature = "custom-auth")]
+impl DeviceCode {
+    /// Initializes a new `DeviceCode` instance.
+    ///
+    /// This method starts the device code authentication process by making an asynchronous request
+    /// to the authentication server. It returns a future that resolves to a `Result` containing the
+    /// `DeviceCode` instance on success or an error if the request fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The client ID obtained from the Minecraft authentication service.
+    ///
+    /// # Returns
+    ///
+    /// * `impl async_trait_alias::AsyncSendSync<Result<Self, AuthError>>` - A future that resolves to a `Result` containing the `DeviceCode` instance or an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AuthError::DeviceCode`] if the device code could not be requested.
+    pub fn new(client_id: &str) -> impl async_trait_alias::AsyncSendSync<Result<Self, AuthError>> {
+        Self::with_config(client_id, AuthConfig::default())
+    }
+
+    /// Initializes a new `DeviceCode` instance using the given HTTP client and endpoints.
+    ///
+    /// This works like [`DeviceCode::new`], and the config is kept for launching.
+    ///
+    /// # Arguments
+    ///
+    /// * `client_id` - The client ID obtained from the Minecraft authentication service.
+    /// * `config` - The client and endpoints to use.
+    ///
+    /// # Returns
+    ///
+    /// * `impl async_trait_alias::AsyncSendSync<Result<Self, AuthError>>` - A future that resolves to a `Result` containing the `DeviceCode` instance or an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AuthError::DeviceCode`] if the device code could not be requested.
+    #[must_use]
+    pub fn with_config(
+        client_id: &str,
+        config: AuthConfig,
+    ) -> impl async_trait_alias::AsyncSendSync<Result<Self, AuthError>> {
+        println!("{EXPERIMENTAL_MESSAGE}");
+        let client_id_str = client_id.to_string();
+        async move {
+            // The code's lifetime starts when it is issued, not when the sign in is launched.
+            let issued_at = std::time::Instant::now();
+            let response_data = code::device_authentication_code(&config, &client_id_str).await?;
+
+            Ok(Self {
+                url: response_data.verification_uri,
+                message: response_data.message,
+                expires_in: response_data.expires_in,
+                expires_at: issued_at + Duration::from_secs(response_data.expires_in.into()),
+                interval: response_data.interval,
+                user_code: response_data.user_code,
+                device_code: response_data.device_code,
+                client_id: client_id_str,
+                config,
+            })
+        }
+    }
+
+    /// Provides pre-launch information.
+    ///
+    /// This method returns a tuple containing the verification URL, the message to display to the user,
+    /// the expiration time of the device code, and the user code. This information is useful for guiding
+    /// the user through the device code authentication process.
+    ///
+    /// # Returns
+    ///
+    /// * `(&str, &str, u32, &str)` - A tuple containing the verification URL, the message, the expiration time, and the user code.
+    #[must_use]
+    pub fn preinfo(&self) -> (&str, &str, u32, &str) {
+        (&self.url, &self.message, self.expires_in, &self.user_code)
+    }
+
+    /// Launches Minecraft using the device code authentication process.
+    ///
+    /// This method completes the device code authentication process by polling the token endpoint
+    /// until the user has entered the code, honouring the polling interval and expiry given by the
+    /// authentication service. It then uses the obtained token to launch Minecraft: Java Edition.
+    /// Use [`DeviceCode::launch_bedrock`] for Bedrock Edition.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CustomAuthData, AuthError>` - A result containing the authentication data or an error if the process fails.
+    ///   A [`errors::MojangError::NotOwned`] is returned if the account doesn't own the game, carrying
+    ///   the Minecraft access token so the game can still be launched in demo mode.
+    ///   A [`errors::DeviceCodeError`] is returned if the code expires or the user declines the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] for the stage that failed, if the code expires, the user declines the request or any authentication step fails.
+    pub async fn launch(&self) -> Result<CustomAuthData, AuthError> {
+        let token = self.authenticate().await?;
+
+        minecraft_token(&self.config, &token.token, xbox::TokenKind::Azure).await
+    }
+
+    /// Launches the device code authentication process for Bedrock Edition and Realms.
+    ///
+    /// This works like [`DeviceCode::launch`], but authorizes the user for Bedrock Realms instead of
+    /// Minecraft services.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BedrockAuthData, AuthError>` - A result containing the XSTS token for Bedrock Realms or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] for the stage that failed, if the code expires, the user declines the request or any authentication step fails.
+    pub async fn launch_bedrock(&self) -> Result<BedrockAuthData, AuthError> {
+        let token = self.authenticate().await?;
+
+        bedrock_token(&self.config, &token.token, xbox::TokenKind::Azure).await
+    }
+
+    /// Polls until the user has entered the code.
+    async fn authenticate(&self) -> Result<code::CodeInfo, AuthError> {
+        Ok(code::authenticate_device(
+            &self.config,
+            &self.device_code,
+            &self.client_id,
+            self.interval,
+            self.expires_at,
+        )
+        .await?)
+    }
+
+    /// Launches the device code authentication process and returns a persistable session.
+    ///
+    /// This works like [`DeviceCode::launch`] for Java Edition, but keeps the Microsoft refresh token and
+    /// records absolute expiry times, so the session can be saved and resumed with [`DeviceCode::refresh_session`].
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuthSession, AuthError>` - A result containing the session or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the code expires, the user declines the request or any authentication step fails.
+    pub async fn launch_session(&self) -> Result<AuthSession, AuthError> {
+        let token = self.authenticate().await?;
+
+        java_session(
+            &self.config,
+            &token.token,
+            xbox::TokenKind::Azure,
+            token.refresh_token,
+            token.expires_in.into(),
+        )
+        .await
+    }
+
+    /// Refreshes the device code authentication process.
+    ///
+    /// This method redeems a Microsoft refresh token for a new access token and then re-runs the
+    /// Xbox Live and Minecraft authentication. Unlike [`DeviceCode::new`] it does not request a new
+    /// device code, so the user isn't asked to sign in again. The client ID and config of this
+    /// instance are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh_token` - The refresh token obtained from a previous sign in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(CustomAuthData, String), AuthError>` - A result containing the refreshed authentication data
+    ///   and the rotated refresh token, which replaces the one passed in, or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh token was rejected or any later authentication step fails.
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+    ) -> Result<(CustomAuthData, String), AuthError> {
+        let token = self.redeem_refresh_token(refresh_token).await?;
+        let auth =
+            minecraft_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await?;
+
+        Ok((auth, token.refresh_token))
+    }
+
+    /// Refreshes the device code authentication process for Bedrock Edition and Realms.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh_token` - The refresh token obtained from a previous sign in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(BedrockAuthData, String), AuthError>` - A result containing the refreshed Bedrock authentication data
+    ///   and the rotated refresh token, which replaces the one passed in, or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh token was rejected or any later authentication step fails.
+    pub async fn refresh_bedrock(
+        &self,
+        refresh_token: &str,
+    ) -> Result<(BedrockAuthData, String), AuthError> {
+        let token = self.redeem_refresh_token(refresh_token).await?;
+        let auth = bedrock_token(&self.config, &token.access_token, xbox::TokenKind::Azure).await?;
+
+        Ok((auth, token.refresh_token))
+    }
+
+    /// Refreshes a saved session.
+    ///
+    /// This method redeems the session's refresh token and re-runs the Xbox Live and Minecraft
+    /// authentication, returning a new session with a rotated refresh token and fresh expiry times.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The session to refresh.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuthSession, AuthError>` - A result containing the refreshed session or an error if the process fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh token was rejected or any later authentication step fails.
+    pub async fn refresh_session(&self, session: &AuthSession) -> Result<AuthSession, AuthError> {
+        let token = self.redeem_refresh_token(&session.refresh_token).await?;
+
+        java_session(
+            &self.config,
+            &token.access_token,
+            xbox::TokenKind::Azure,
+            token.refresh_token,
+            token.expires_in.into(),
+        )
+        .await
+    }
+
+    /// Redeems a refresh token issued to this client ID, which has no client secret.
+    async fn redeem_refresh_token(&self, refresh_token: &str) -> Result<oauth::Token, AuthError> {
+        Ok(oauth::refresh(
+            &self.config,
+            refresh_token,
+            &oauth::Params::new(&self.client_id),
+            None,
+        )
+        .await?)
+    }
+}
+
+/// Offline Authentication
+///
+/// This struct represents an offline mode account, for playing on servers with `online-mode=false`
+/// or testing against offline dev servers. No account is signed in to, the username is only checked
+/// against Minecraft's rules and the UUID is derived from it the same way the vanilla server does.
+#[cfg(feature = "custom-auth")]
+pub struct Offline {
+    username: String,
+    uuid: String,
+}
+
+#[cfg(feature = "custom-auth")]
+impl Offline {
+    /// Initializes a new `Offline` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The player's name, 3 to 16 letters, digits or underscores.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, errors::OfflineError>` - The account, or an error if the username isn't valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`errors::OfflineError`] if the username is too short, too long or contains other characters.
+    pub fn new(username: &str) -> Result<Self, errors::OfflineError> {
+        offline::validate(username)?;
+
+        Ok(Self {
+            username: username.to_string(),
+            uuid: offline::uuid(username),
+        })
+    }
+
+    /// Retrieves the offline UUID of the account, without dashes.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The UUID the vanilla server gives the player in offline mode.
+    #[must_use]
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// Creates the authentication data for launching the game.
+    ///
+    /// The access token is a placeholder, offline mode servers don't check it.
+    ///
+    /// # Returns
+    ///
+    /// * `CustomAuthData` - The authentication data, which never expires.
+    #[must_use]
+    pub fn launch(&self) -> CustomAuthData {
+        CustomAuthData {
+            access_token: "0".to_string(),
+            uuid: self.uuid.clone(),
+            expires_in: i32::MAX,
+            profile: MinecraftProfile {
+                id: self.uuid.clone(),
+                name: self.username.clone(),
+                skins: Vec::new(),
+                capes: Vec::new(),
+            },
+            xbox_identity: None,
+            entitlements: Entitlements::default(),
+        }
+    }
+}
+
+/// Yggdrasil Authentication
+///
+/// This struct signs in to third-party auth servers that speak the legacy Yggdrasil protocol, such
+/// as self-hosted authlib-injector compatible servers. Accounts sign in with a username, or email,
+/// and password, and the server picks the profile that is launched with.
+///
+/// Every request carries a client token, which identifies the launcher to the server. Access tokens
+/// can only be refreshed or revoked with the client token they were issued to, so launchers should
+/// save theirs and reuse it.
+#[cfg(feature = "custom-auth")]
+pub struct Yggdrasil {
+    api_root: String,
+    client_token: String,
+    config: AuthConfig,
+}
+
+#[cfg(feature = "custom-auth")]
+impl Yggdrasil {
+    /// Initializes a new `Yggdrasil` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_root` - The server's API root, under which the `/authserver` endpoints are served.
+    /// * `client_token` - The launcher's client token.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new instance of `Yggdrasil` for the server.
+    #[must_use]
+    pub fn new(api_root: &str, client_token: &str) -> Self {
+        Self {
+            api_root: api_root.trim_end_matches('/').to_string(),
+            client_token: client_token.to_string(),
+            config: AuthConfig::new(),
+        }
+    }
+
+    /// Sets the HTTP client used to sign in.
+    ///
+    /// Yggdrasil servers are third-party, so only the config's client is used, requests always go
+    /// to the API root.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The config whose client to use.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The `Yggdrasil` instance using the given config.
+    #[must_use]
+    pub fn config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Follows the server's `ALI` (API Location Indication) header to its API root.
+    ///
+    /// Users often only know the address of a server's website. Servers that serve their API
+    /// elsewhere point at it with the `X-Authlib-Injector-API-Location` header, which is followed
+    /// here. Servers that don't send the header are assumed to serve the API at the given URL.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, errors::YggdrasilError>` - The instance using the discovered API root.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::YggdrasilError::ResponseError`] if the URL is invalid or the server can't be reached.
+    pub async fn discover(mut self) -> Result<Self, errors::YggdrasilError> {
+        self.api_root = yggdrasil::discover(&self.config.client, &self.api_root).await?;
+
+        Ok(self)
+    }
+
+    /// Retrieves the server's API root.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The API root, without a trailing slash.
+    #[must_use]
+    pub fn api_root(&self) -> &str {
+        &self.api_root
+    }
+
+    /// Signs in with a username, or email, and password.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The account's username or email.
+    /// * `password` - The account's password.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CustomAuthData, errors::YggdrasilError>` - The authentication data of the selected profile.
+    ///   Yggdrasil tokens have no fixed lifetime, so `expires_in` is `i32::MAX`, use
+    ///   [`Yggdrasil::validate`] to check whether a token is still valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::YggdrasilError::Status`] if the credentials are wrong, or
+    /// [`errors::YggdrasilError::NoProfile`] if the account has no profile selected.
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<CustomAuthData, errors::YggdrasilError> {
+        yggdrasil::authenticate(
+            &self.config.client,
+            &self.api_root,
+            username,
+            password,
+            &self.client_token,
+        )
+        .await
+    }
+
+    /// Exchanges an access token for a new one, which revokes the old token.
+    ///
+    /// # Arguments
+    ///
+    /// * `access_token` - An access token issued to this client token.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CustomAuthData, errors::YggdrasilError>` - The authentication data with the new token.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::YggdrasilError::Status`] if the token has been revoked or was issued to
+    /// another client token.
+    pub async fn refresh(
+        &self,
+        access_token: &str,
+    ) -> Result<CustomAuthData, errors::YggdrasilError> {
+        yggdrasil::refresh(
+            &self.config.client,
+            &self.api_root,
+            access_token,
+            &self.client_token,
+        )
+        .await
+    }
+
+    /// Checks whether an access token can still be used to join servers.
+    ///
+    /// # Arguments
+    ///
+    /// * `access_token` - An access token issued to this client token.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, errors::YggdrasilError>` - Whether the token is valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`errors::YggdrasilError`] if the server can't be reached or answers unexpectedly.
+    pub async fn validate(&self, access_token: &str) -> Result<bool, errors::YggdrasilError> {
+        yggdrasil::validate(
+            &self.config.client,
+            &self.api_root,
+            access_token,
+            &self.client_token,
+        )
+        .await
+    }
+
+    /// Revokes an access token, such as when the user signs out of the launcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `access_token` - An access token issued to this client token.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), errors::YggdrasilError>` - `Ok` once the token is revoked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`errors::YggdrasilError`] if the server can't be reached or refuses the request.
+    pub async fn invalidate(&self, access_token: &str) -> Result<(), errors::YggdrasilError> {
+        yggdrasil::invalidate(
+            &self.config.client,
+            &self.api_root,
+            access_token,
+            &self.client_token,
+        )
+        .await
+    }
+
+    /// Revokes every access token of an account, on every launcher it signed in to.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The account's username or email.
+    /// * `password` - The account's password.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), errors::YggdrasilError>` - `Ok` once every token is revoked.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`errors::YggdrasilError::Status`] if the credentials are wrong.
+    pub async fn signout(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<(), errors::YggdrasilError> {
+        yggdrasil::signout(&self.config.client, &self.api_root, username, password).await
+    }
+}
+
+/// Returns the user hash from an Xbox Live token's display claims.
+#[cfg(feature = "custom-auth")]
+fn user_hash(xbox: &xbox::XblOutput) -> Result<&str, errors::XboxError> {
+    xbox.display_claims.uhs().ok_or_else(|| {
+        errors::XboxError::ResponseError("No user hash in display claims".to_string())
+    })
+}
+
+/// Exchanges a Microsoft access token for Xbox Live tokens and a Minecraft token.
+#[cfg(feature = "custom-auth")]
+async fn minecraft_token(
+    config: &AuthConfig,
+    access_token: &str,
+    kind: xbox::TokenKind,
+) -> Result<CustomAuthData, AuthError> {
+    let xbox = xbox::xbl(config, access_token, kind).await?;
+    let xts = xbox::xsts_token(config, &xbox.token, xbox::RelyingParty::Java).await?;
+    let mut auth = mojang::token(config, user_hash(&xbox)?, &xts.token).await?;
+    auth.xbox_identity = xbox_identity(config, &xbox.token).await?;
+
+    Ok(auth)
+}
+
+/// Fetches who the user is on Xbox Live, if the config asks for it.
+///
+/// Returns `None` without a request when the identity wasn't asked for, or if Xbox Live left out
+/// the XUID or gamertag.
+#[cfg(feature = "custom-auth")]
+async fn xbox_identity(
+    config: &AuthConfig,
+    xbl_token: &str,
+) -> Result<Option<XboxIdentity>, errors::XTSError> {
+    if !config.xbox_identity {
+        return Ok(None);
+    }
+    let xts = xbox::xsts_token(config, xbl_token, xbox::RelyingParty::XboxLive).await?;
+
+    Ok(xts
+        .display_claims
+        .xui
+        .into_iter()
+        .next()
+        .and_then(XboxIdentity::from_xui))
+}
+
+/// Exchanges a Microsoft access token for Xbox Live tokens and an XSTS token for Bedrock Realms.
+#[cfg(feature = "custom-auth")]
+async fn bedrock_token(
+    config: &AuthConfig,
+    access_token: &str,
+    kind: xbox::TokenKind,
+) -> Result<BedrockAuthData, AuthError> {
+    let xbox = xbox::xbl(config, access_token, kind).await?;
+    let xts = xbox::xsts_token(config, &xbox.token, xbox::RelyingParty::Bedrock).await?;
+
+    Ok(BedrockAuthData::from_xsts(xts)?)
+}
+
+/// Exchanges a Microsoft access token for a Java Edition session, recording when each token expires.
+#[cfg(feature = "custom-auth")]
+async fn java_session(
+    config: &AuthConfig,
+    access_token: &str,
+    kind: xbox::TokenKind,
+    refresh_token: String,
+    expires_in: u64,
+) -> Result<AuthSession, AuthError> {
+    let now = session::now();
+    let xbox = xbox::xbl(config, access_token, kind).await?;
+    let xts = xbox::xsts_token(config, &xbox.token, xbox::RelyingParty::Java).await?;
+    let auth = mojang::token(config, user_hash(&xbox)?, &xts.token).await?;
+    let xbox_identity = xbox_identity(config, &xbox.token).await?;
+
+    Ok(AuthSession {
+        access_token: auth.access_token,
+        uuid: auth.uuid,
+        profile: auth.profile,
+        xbox_identity,
+        entitlements: auth.entitlements,
+        refresh_token,
+        token_kind: kind,
+        tenant: oauth::default_tenant(),
+        scope: oauth::default_scope(),
+        msa_expires_at: now + expires_in,
+        xbl_expires_at: xbox::timestamp(&xbox.not_after).unwrap_or(now),
+        xsts_expires_at: xbox::timestamp(&xts.not_after).unwrap_or(now),
+        minecraft_expires_at: now + u64::try_from(auth.expires_in).unwrap_or_default(),
+    })
 }
 
 /// `Launch` struct represents the configuration for launching a Minecraft client.
@@ -23,7 +23,8 @@ enum Commands {
 #[derive(Args)]
 struct OauthArgs {
     client_id: String,
-    client_secret: String,
+    /// Client secret, only needed for confidential Azure apps. Pass "" to skip it and still set a port.
+    client_secret: Option<String>,
     port: Option<u16>,
     bedrockrelm: Option<bool>,
 }
@@ -60,12 +61,33 @@ async fn main() {
 }
 
 async fn handle_oauth(oauth_args: &OauthArgs) {
+    let client_secret = oauth_args.client_secret.as_deref().filter(|secret| !secret.is_empty());
     let auth = Oauth::new(&oauth_args.client_id, Some(oauth_args.port.unwrap_or(8000)));
     println!("URL: {} \nWaiting for Login........", auth.url());
-    let auth_info = auth.launch(false, &oauth_args.client_secret).await.unwrap();
+    if oauth_args.bedrockrelm == Some(true) {
+        let auth_info = auth
+            .launch_bedrock(client_secret)
+            .await
+            .unwrap();
+        println!(
+            "XstsToken: {:?}, \n UserHash: {:?}, \n Gamertag: {:?}, \n ExpiresAt: {:?}",
+            auth_info.xsts_token,
+            auth_info.user_hash,
+            auth_info.gamertag,
+            auth_info.expires_at
+        );
+        return;
+    }
+    let auth_info = auth
+        .launch(client_secret)
+        .await
+        .unwrap();
     println!(
-        "Bearer: {:?}, \n UUID: {:?}, \n Expire_in: {:?}, \n XtsToken: {:?}",
-        auth_info.access_token, auth_info.uuid, auth_info.expires_in, auth_info.xts_token
+        "Bearer: {:?}, \n UUID: {:?}, \n Username: {:?}, \n Expire_in: {:?}",
+        auth_info.access_token,
+        auth_info.uuid,
+        auth_info.profile.name,
+        auth_info.expires_in
     );
 }
 
@@ -0,0 +1,287 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use std::{fmt, time::Duration};
+
+use super::store::{AccountStore, Contents};
+use crate::{
+    custom::{oauth, xbox::TokenKind},
+    errors::{AccountError, AuthError, StoreError},
+    java_session, AuthConfig, AuthSession, DeviceCode, LiveLogin, Oauth,
+};
+
+/// How long before the Minecraft token expires a session is refreshed by default, in seconds.
+const REFRESH_MARGIN_SECS: u64 = 5 * 60;
+
+/// A saved account, as listed by [`AccountManager::accounts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// The player's Minecraft UUID, which identifies the account.
+    pub uuid: String,
+    /// The player's Minecraft name.
+    pub name: String,
+    /// The Xbox Live gamertag, if the config asked for the Xbox Live identity.
+    pub gamertag: Option<String>,
+    /// Whether this is the active account.
+    pub active: bool,
+}
+
+/// Keeps track of several signed in accounts and which of them is active.
+///
+/// Accounts are saved to an [`AccountStore`] whenever they change. Every account is refreshed
+/// with the same client ID, so the [`Oauth`] and [`DeviceCode`] sign ins passed to the manager
+/// must use the client ID it was opened with. The tenant and scopes are kept from each sign in.
+/// Sessions from [`LiveLogin`] are refreshed the way the official launcher does it instead.
+pub struct AccountManager {
+    store: AccountStore,
+    client_id: String,
+    client_secret: Option<String>,
+    config: AuthConfig,
+    refresh_margin: Duration,
+    contents: Contents,
+}
+
+impl fmt::Debug for AccountManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountManager")
+            .field("store", &self.store)
+            .field("client_id", &self.client_id)
+            .field("config", &self.config)
+            .field("refresh_margin", &self.refresh_margin)
+            .field("accounts", &self.contents.sessions.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AccountManager {
+    /// Opens the accounts saved in `store`.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - Where the accounts are saved.
+    /// * `client_id` - The client ID the accounts were signed in with.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, StoreError>` - The manager, or an error if the store couldn't be read.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StoreError`] if the store can't be read or decrypted.
+    pub fn open(store: AccountStore, client_id: &str) -> Result<Self, StoreError> {
+        let contents = store.read()?;
+
+        Ok(Self {
+            store,
+            client_id: client_id.to_string(),
+            client_secret: None,
+            config: AuthConfig::default(),
+            refresh_margin: Duration::from_secs(REFRESH_MARGIN_SECS),
+            contents,
+        })
+    }
+
+    /// Sets the client secret used when refreshing, for confidential clients.
+    #[must_use]
+    pub fn client_secret(mut self, client_secret: &str) -> Self {
+        self.client_secret = Some(client_secret.to_string());
+        self
+    }
+
+    /// Sets the HTTP client and endpoints used when refreshing.
+    #[must_use]
+    pub fn config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets how long before the Minecraft token expires a session is refreshed, five minutes by default.
+    #[must_use]
+    pub fn refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Signs in with OAuth and saves the account.
+    ///
+    /// The account becomes active if no account was active before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AccountError`] if the sign in fails or the store can't be written.
+    pub async fn add_oauth(&mut self, oauth: &Oauth) -> Result<Account, AccountError> {
+        let session = oauth.launch_session(self.client_secret.as_deref()).await?;
+
+        Ok(self.add(session)?)
+    }
+
+    /// Signs in with a device code and saves the account.
+    ///
+    /// The account becomes active if no account was active before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AccountError`] if the sign in fails or the store can't be written.
+    pub async fn add_device_code(&mut self, code: &DeviceCode) -> Result<Account, AccountError> {
+        let session = code.launch_session().await?;
+
+        Ok(self.add(session)?)
+    }
+
+    /// Saves a session, replacing any saved session of the same account.
+    ///
+    /// The account becomes active if no account was active before.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StoreError`] if the store can't be written.
+    pub fn add(&mut self, session: AuthSession) -> Result<Account, StoreError> {
+        if self.contents.active.is_none() {
+            self.contents.active = Some(session.uuid.clone());
+        }
+        let account = self.account(&session);
+        match self.position(&session.uuid) {
+            Some(index) => self.contents.sessions[index] = session,
+            None => self.contents.sessions.push(session),
+        }
+        self.store.write(&self.contents)?;
+
+        Ok(account)
+    }
+
+    /// Lists the saved accounts.
+    #[must_use]
+    pub fn accounts(&self) -> Vec<Account> {
+        self.contents
+            .sessions
+            .iter()
+            .map(|session| self.account(session))
+            .collect()
+    }
+
+    fn account(&self, session: &AuthSession) -> Account {
+        Account {
+            uuid: session.uuid.clone(),
+            name: session.profile.name.clone(),
+            gamertag: session
+                .xbox_identity
+                .as_ref()
+                .map(|identity| identity.gamertag.clone()),
+            active: self.contents.active.as_ref() == Some(&session.uuid),
+        }
+    }
+
+    /// Removes a saved account.
+    ///
+    /// If it was the active account, no account is active afterwards.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, StoreError>` - Whether an account was removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StoreError`] if the store can't be written.
+    pub fn remove(&mut self, uuid: &str) -> Result<bool, StoreError> {
+        let Some(index) = self.position(uuid) else {
+            return Ok(false);
+        };
+        self.contents.sessions.remove(index);
+        if self.contents.active.as_deref() == Some(uuid) {
+            self.contents.active = None;
+        }
+        self.store.write(&self.contents)?;
+
+        Ok(true)
+    }
+
+    /// Makes a saved account the active one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AccountError::UnknownAccount`] if no saved account has the UUID, or an
+    /// [`AccountError::Store`] if the store can't be written.
+    pub fn set_active(&mut self, uuid: &str) -> Result<(), AccountError> {
+        if self.position(uuid).is_none() {
+            return Err(AccountError::UnknownAccount(uuid.to_string()));
+        }
+        self.contents.active = Some(uuid.to_string());
+
+        Ok(self.store.write(&self.contents)?)
+    }
+
+    /// Returns the saved session of the active account, which may have expired.
+    #[must_use]
+    pub fn active(&self) -> Option<&AuthSession> {
+        let uuid = self.contents.active.as_deref()?;
+        self.position(uuid)
+            .map(|index| &self.contents.sessions[index])
+    }
+
+    /// Returns a session of the active account that is safe to launch with.
+    ///
+    /// If the Minecraft token expires within the refresh margin the session is refreshed first,
+    /// and the refreshed session is saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AccountError::NoActiveAccount`] if no account is active, or an [`AccountError`]
+    /// if refreshing fails or the store can't be written.
+    pub async fn get_valid_session(&mut self) -> Result<AuthSession, AccountError> {
+        let index = self
+            .contents
+            .active
+            .as_deref()
+            .and_then(|uuid| self.position(uuid))
+            .ok_or(AccountError::NoActiveAccount)?;
+        let session = &self.contents.sessions[index];
+        if !session.needs_refresh(self.refresh_margin) {
+            return Ok(session.clone());
+        }
+
+        let session = self.refresh(session).await?;
+        self.contents.sessions[index] = session.clone();
+        self.store.write(&self.contents)?;
+
+        Ok(session)
+    }
+
+    async fn refresh(&self, session: &AuthSession) -> Result<AuthSession, AuthError> {
+        // Accounts signed in with the Xbox Live identity keep it, so their gamertag isn't lost.
+        let config = self
+            .config
+            .clone()
+            .xbox_identity(self.config.xbox_identity || session.xbox_identity.is_some());
+        if session.token_kind == TokenKind::Live {
+            return LiveLogin::new()
+                .config(config)
+                .refresh_session(session)
+                .await;
+        }
+        let params = session.params(&self.client_id);
+        let token = oauth::refresh(
+            &config,
+            &session.refresh_token,
+            &params,
+            self.client_secret.as_deref(),
+        )
+        .await?;
+        let session = java_session(
+            &config,
+            &token.access_token,
+            TokenKind::Azure,
+            token.refresh_token,
+            token.expires_in.into(),
+        )
+        .await?;
+
+        Ok(session.with_params(&params))
+    }
+
+    fn position(&self, uuid: &str) -> Option<usize> {
+        self.contents
+            .sessions
+            .iter()
+            .position(|session| session.uuid == uuid)
+    }
+}
@@ -0,0 +1,248 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    custom::{
+        oauth::{self, check_info, parse_info},
+        send_json,
+        xbox::TokenKind,
+    },
+    errors::{AuthError, OAuthError, TokenError},
+    java_session, minecraft_token, AuthConfig, AuthSession, CustomAuthData,
+};
+
+/// The client ID of the official Minecraft launcher.
+pub const CLIENT_ID: &str = "00000000402b5328";
+/// The scope the official launcher signs in with.
+const SCOPE: &str = "service::user.auth.xboxlive.com::MBI_SSL";
+
+#[derive(Deserialize, Debug)]
+struct LiveToken {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+/// Microsoft sign in the way the official launcher does it, without an Azure app of your own.
+///
+/// The user signs in on `login.live.com` in a web view owned by the launcher. Once they are done the
+/// web view is sent to `oauth20_desktop.srf`, which shows a blank page. The launcher watches for that
+/// redirect with [`LiveLogin::is_redirect`] and hands its URL, which carries the authorization code,
+/// to [`LiveLogin::launch`].
+#[derive(Debug, Clone)]
+pub struct LiveLogin {
+    client_id: String,
+    pub(crate) state: String,
+    config: AuthConfig,
+    url: String,
+}
+
+impl Default for LiveLogin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveLogin {
+    /// Initializes a new `LiveLogin` with the official launcher's client ID.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_client_id(CLIENT_ID)
+    }
+
+    /// Initializes a new `LiveLogin` with another client ID registered for `login.live.com`.
+    #[must_use]
+    pub fn with_client_id(client_id: &str) -> Self {
+        let mut login = Self {
+            client_id: client_id.to_string(),
+            state: oauth::state(),
+            config: AuthConfig::default(),
+            url: String::new(),
+        };
+        login.url = login.authorize_url();
+        login
+    }
+
+    /// Sets the HTTP client and endpoints used to sign in.
+    ///
+    /// The authorization URL is rebuilt to use the configured `login.live.com` endpoint.
+    #[must_use]
+    pub fn config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self.url = self.authorize_url();
+        self
+    }
+
+    fn authorize_url(&self) -> String {
+        format!(
+            "{}/oauth20_authorize.srf?client_id={}&response_type=code&scope={SCOPE}&redirect_uri={}&state={}",
+            self.config.endpoints.live,
+            self.client_id,
+            self.redirect_uri(),
+            self.state
+        )
+    }
+
+    /// Retrieves the authorization URL to open in the web view.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Retrieves the URL the web view is redirected to once the user has signed in.
+    #[must_use]
+    pub fn redirect_uri(&self) -> String {
+        format!("{}/oauth20_desktop.srf", self.config.endpoints.live)
+    }
+
+    /// Returns `true` if the web view has reached the final redirect, so its URL can be passed to
+    /// [`LiveLogin::launch`] and the web view closed.
+    #[must_use]
+    pub fn is_redirect(&self, url: &str) -> bool {
+        url.strip_prefix(&self.redirect_uri())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('?'))
+    }
+
+    /// Launches Minecraft with the code from the final redirect.
+    ///
+    /// # Arguments
+    ///
+    /// * `redirect_url` - The URL the web view was redirected to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CustomAuthData, AuthError>` - The authentication data for Minecraft: Java Edition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError::OAuth`] if the redirect carries an error, a mismatched state or no
+    /// code, or an [`AuthError`] for whichever later stage failed.
+    pub async fn launch(&self, redirect_url: &str) -> Result<CustomAuthData, AuthError> {
+        let token = self.token(redirect_url).await?;
+
+        minecraft_token(&self.config, &token.access_token, TokenKind::Live).await
+    }
+
+    /// Launches Minecraft with the code from the final redirect and returns a persistable session.
+    ///
+    /// # Arguments
+    ///
+    /// * `redirect_url` - The URL the web view was redirected to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuthSession, AuthError>` - The session, which can be resumed with [`LiveLogin::refresh_session`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] for whichever stage failed.
+    pub async fn launch_session(&self, redirect_url: &str) -> Result<AuthSession, AuthError> {
+        let token = self.token(redirect_url).await?;
+
+        java_session(
+            &self.config,
+            &token.access_token,
+            TokenKind::Live,
+            token.refresh_token,
+            token.expires_in,
+        )
+        .await
+    }
+
+    /// Refreshes a previous sign in without showing the web view again.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh_token` - The refresh token of a previous sign in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(CustomAuthData, String), AuthError>` - The refreshed authentication data and the rotated refresh token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] if the refresh token was rejected or any later stage failed.
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+    ) -> Result<(CustomAuthData, String), AuthError> {
+        let token = self
+            .request(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ])
+            .await?;
+        let auth = minecraft_token(&self.config, &token.access_token, TokenKind::Live).await?;
+
+        Ok((auth, token.refresh_token))
+    }
+
+    /// Refreshes a saved session.
+    ///
+    /// This method redeems the session's refresh token and re-runs the Xbox Live and Minecraft
+    /// authentication, returning a new session with a rotated refresh token and fresh expiry times.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - A session from [`LiveLogin::launch_session`].
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuthSession, AuthError>` - The refreshed session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError`] if the refresh token was rejected or any later stage failed.
+    pub async fn refresh_session(&self, session: &AuthSession) -> Result<AuthSession, AuthError> {
+        let token = self
+            .request(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &session.refresh_token),
+            ])
+            .await?;
+
+        java_session(
+            &self.config,
+            &token.access_token,
+            TokenKind::Live,
+            token.refresh_token,
+            token.expires_in,
+        )
+        .await
+    }
+
+    /// Redeems the code carried by the final redirect.
+    async fn token(&self, redirect_url: &str) -> Result<LiveToken, AuthError> {
+        let url =
+            Url::parse(redirect_url).map_err(|err| OAuthError::ParseError(err.to_string()))?;
+        let info = check_info(parse_info(url.query().unwrap_or_default())?, &self.state)?;
+        let code = info
+            .code
+            .ok_or_else(|| OAuthError::ParseError("No code in the redirect URL".to_string()))?;
+
+        Ok(self
+            .request(&[("grant_type", "authorization_code"), ("code", &code)])
+            .await?)
+    }
+
+    async fn request(&self, grant: &[(&str, &str)]) -> Result<LiveToken, TokenError> {
+        let redirect_uri = self.redirect_uri();
+        let mut form = vec![
+            ("client_id", self.client_id.as_str()),
+            ("scope", SCOPE),
+            ("redirect_uri", &redirect_uri),
+        ];
+        form.extend_from_slice(grant);
+
+        Ok(send_json(
+            self.config
+                .client
+                .post(format!("{}/oauth20_token.srf", self.config.endpoints.live))
+                .form(&form),
+        )
+        .await?)
+    }
+}
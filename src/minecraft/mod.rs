@@ -0,0 +1,5 @@
+pub mod accounts;
+pub mod live;
+pub mod session_server;
+pub mod sisu;
+pub mod store;
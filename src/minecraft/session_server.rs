@@ -0,0 +1,201 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use ring::digest::{Context, SHA1_FOR_LEGACY_USE_ONLY};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::IpAddr;
+
+use crate::{
+    custom::{send_empty, HttpError},
+    errors::SessionError,
+    AuthConfig, CustomAuthData,
+};
+
+/// A player's profile as returned by the session server, with signed properties such as their textures.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GameProfile {
+    /// The player's UUID, without dashes.
+    pub id: String,
+    /// The player's name.
+    pub name: String,
+    /// The profile's properties, such as `textures`.
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// A property of a [`GameProfile`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProfileProperty {
+    /// The property's name, such as `textures`.
+    pub name: String,
+    /// The property's value, base64 encoded JSON for `textures`.
+    pub value: String,
+    /// Mojang's signature of the value, base64 encoded.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// Joins and verifies online mode servers through the Minecraft session server.
+///
+/// When a client connects to an online mode server, both sides compute the same
+/// [`SessionServer::server_hash`]. The client tells the session server it is joining with
+/// [`SessionServer::join`], then the server, or a proxy in front of it, checks that it did with
+/// [`SessionServer::has_joined`] before letting the player in.
+#[derive(Debug, Clone, Default)]
+pub struct SessionServer {
+    config: AuthConfig,
+}
+
+impl SessionServer {
+    /// Creates a session server client using a new HTTP client and the public endpoints.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the HTTP client and endpoints used.
+    #[must_use]
+    pub fn config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Computes the server hash both the client and the server send to the session server.
+    ///
+    /// The hash is the SHA-1 of the server ID, the shared secret and the server's public key, read as
+    /// a signed two's complement number and formatted as hex without leading zeros, so it may start
+    /// with a `-`.
+    ///
+    /// # Arguments
+    ///
+    /// * `server_id` - The server ID from the encryption request, empty for vanilla servers.
+    /// * `shared_secret` - The shared secret the client generated.
+    /// * `public_key` - The server's DER encoded public key from the encryption request.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The server hash.
+    #[must_use]
+    pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+        let mut context = Context::new(&SHA1_FOR_LEGACY_USE_ONLY);
+        context.update(server_id.as_bytes());
+        context.update(shared_secret);
+        context.update(public_key);
+        let mut hash = context.finish().as_ref().to_vec();
+
+        let negative = hash[0] & 0x80 != 0;
+        if negative {
+            let mut carry = true;
+            for byte in hash.iter_mut().rev() {
+                (*byte, carry) = (!*byte).overflowing_add(u8::from(carry));
+            }
+        }
+        let digest = hex::encode(hash);
+        let digest = match digest.trim_start_matches('0') {
+            "" => "0",
+            digest => digest,
+        };
+
+        if negative {
+            format!("-{digest}")
+        } else {
+            digest.to_string()
+        }
+    }
+
+    /// Tells the session server the player is joining a server, which the client does before
+    /// answering the server's encryption request.
+    ///
+    /// # Arguments
+    ///
+    /// * `auth` - The authentication data of the player joining.
+    /// * `server_hash` - The [`SessionServer::server_hash`] of the connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError::Status`] if the session server refuses the access token, such as when
+    /// it has expired or the account doesn't own the game.
+    pub async fn join(&self, auth: &CustomAuthData, server_hash: &str) -> Result<(), SessionError> {
+        let body = json!({
+            "accessToken": auth.access_token,
+            "selectedProfile": auth.uuid,
+            "serverId": server_hash,
+        });
+
+        Ok(send_empty(
+            self.config
+                .client
+                .post(format!(
+                    "{}/session/minecraft/join",
+                    self.config.endpoints.session_server
+                ))
+                .json(&body),
+        )
+        .await?)
+    }
+
+    /// Checks that a player joined through the session server, which the server does once the client
+    /// has answered its encryption request.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The name the player logged in with.
+    /// * `server_hash` - The [`SessionServer::server_hash`] of the connection.
+    /// * `ip` - The player's IP address, to make sure the join came from the same address.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<GameProfile>, SessionError>` - The player's profile, or `None` if they didn't join.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SessionError`] if the session server can't be reached or answers unexpectedly.
+    pub async fn has_joined(
+        &self,
+        username: &str,
+        server_hash: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<Option<GameProfile>, SessionError> {
+        let mut query = vec![
+            ("username", username.to_string()),
+            ("serverId", server_hash.to_string()),
+        ];
+        if let Some(ip) = ip {
+            query.push(("ip", ip.to_string()));
+        }
+
+        let response = self
+            .config
+            .client
+            .get(format!(
+                "{}/session/minecraft/hasJoined",
+                self.config.endpoints.session_server
+            ))
+            .query(&query)
+            .send()
+            .await
+            .map_err(|err| SessionError::ResponseError(err.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|err| SessionError::ResponseError(err.to_string()))?;
+
+        // The session server answers `204 No Content` when the player didn't join.
+        if status == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(HttpError::Status {
+                status: status.as_u16(),
+                body,
+            }
+            .into());
+        }
+
+        serde_json::from_str(&body).map(Some).map_err(|err| {
+            SessionError::ResponseError(format!("Unexpected response ({err}): {body}"))
+        })
+    }
+}
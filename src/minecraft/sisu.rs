@@ -0,0 +1,333 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use rand::RngCore;
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    RequestBuilder,
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use url::{Position, Url};
+
+use crate::{
+    custom::{
+        send_json,
+        xbox::{self, XtsOutput},
+    },
+    errors::{AuthError, XTSError, XboxError},
+    AuthConfig, BedrockAuthData,
+};
+
+/// The signing policy version Xbox Live expects in the `Signature` header.
+const POLICY_VERSION: u32 = 1;
+/// The Unix epoch as a Windows file time, in 100 nanosecond intervals since 1601.
+const UNIX_EPOCH_FILETIME: u64 = 116_444_736_000_000_000;
+
+/// An ECDSA P-256 key that proves signed requests to Xbox Live come from the same device.
+///
+/// Device tokens are bound to the key they were requested with, so a launcher that saves its
+/// device token must also save the key, see [`ProofKey::pkcs8`].
+pub struct ProofKey {
+    key_pair: EcdsaKeyPair,
+    pkcs8: Vec<u8>,
+    rng: SystemRandom,
+}
+
+impl fmt::Debug for ProofKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProofKey")
+            .field("public_key", self.key_pair.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProofKey {
+    /// Generates a new random key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`XboxError::Signing`] if no randomness is available.
+    pub fn generate() -> Result<Self, XboxError> {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .map_err(|_| XboxError::Signing("Generating the key failed".to_string()))?;
+
+        Self::from_pkcs8(pkcs8.as_ref())
+    }
+
+    /// Loads a key saved with [`ProofKey::pkcs8`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`XboxError::Signing`] if the document isn't a P-256 key.
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Self, XboxError> {
+        let rng = SystemRandom::new();
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &rng)
+            .map_err(|err| XboxError::Signing(err.to_string()))?;
+
+        Ok(Self {
+            key_pair,
+            pkcs8: pkcs8.to_vec(),
+            rng,
+        })
+    }
+
+    /// Returns the key as a PKCS#8 document, for saving it.
+    #[must_use]
+    pub fn pkcs8(&self) -> &[u8] {
+        &self.pkcs8
+    }
+
+    /// Returns the public key as the JWK sent in the `ProofKey` of device token and SISU requests.
+    #[must_use]
+    pub fn jwk(&self) -> Value {
+        // The public key is an uncompressed point, `0x04` followed by the x and y coordinates.
+        let point = self.key_pair.public_key().as_ref();
+
+        json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "alg": "ES256",
+            "use": "sig",
+            "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+            "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+        })
+    }
+
+    /// Signs a request, returning the value of its `Signature` header.
+    ///
+    /// The signature covers the policy version, the current time, the method, the path and query,
+    /// the `Authorization` header and the body, so it has to be made for the exact request sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The request method, such as `POST`.
+    /// * `path_and_query` - The path of the request URL and its query, if any.
+    /// * `authorization` - The `Authorization` header of the request, empty if it has none.
+    /// * `body` - The request body.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`XboxError::Signing`] if signing fails.
+    pub fn sign(
+        &self,
+        method: &str,
+        path_and_query: &str,
+        authorization: &str,
+        body: &[u8],
+    ) -> Result<String, XboxError> {
+        let timestamp = filetime();
+        let mut data = Vec::new();
+        for part in [
+            &POLICY_VERSION.to_be_bytes()[..],
+            &timestamp.to_be_bytes(),
+            method.as_bytes(),
+            path_and_query.as_bytes(),
+            authorization.as_bytes(),
+            body,
+        ] {
+            data.extend_from_slice(part);
+            data.push(0);
+        }
+        let signature = self
+            .key_pair
+            .sign(&self.rng, &data)
+            .map_err(|_| XboxError::Signing("Signing the request failed".to_string()))?;
+
+        let mut header = POLICY_VERSION.to_be_bytes().to_vec();
+        header.extend_from_slice(&timestamp.to_be_bytes());
+        header.extend_from_slice(signature.as_ref());
+
+        Ok(STANDARD.encode(header))
+    }
+}
+
+/// An Xbox Live device token, which identifies the device to SISU.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DeviceToken {
+    /// The device token.
+    pub token: String,
+    /// When the token expires, as a Unix timestamp in seconds.
+    pub expires_at: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct DeviceOutput {
+    not_after: String,
+    token: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct SisuOutput {
+    authorization_token: XtsOutput,
+}
+
+/// Signs in to Xbox Live through SISU, the single sign in service Xbox titles use.
+///
+/// Unlike the plain user token exchange, every request is signed with a [`ProofKey`] and carries a
+/// device token, which some titles and Bedrock Edition services require.
+#[derive(Debug)]
+pub struct Sisu {
+    key: ProofKey,
+    device_id: String,
+    config: AuthConfig,
+}
+
+impl Sisu {
+    /// Creates a SISU client that signs its requests with `key`, as a new random device.
+    #[must_use]
+    pub fn new(key: ProofKey) -> Self {
+        let mut id = [0; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+        let id = hex::encode(id);
+
+        Self {
+            key,
+            device_id: format!(
+                "{{{}-{}-{}-{}-{}}}",
+                &id[..8],
+                &id[8..12],
+                &id[12..16],
+                &id[16..20],
+                &id[20..]
+            ),
+            config: AuthConfig::default(),
+        }
+    }
+
+    /// Sets the HTTP client and endpoints used.
+    #[must_use]
+    pub fn config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns the key requests are signed with.
+    #[must_use]
+    pub fn key(&self) -> &ProofKey {
+        &self.key
+    }
+
+    /// Requests a device token for this device.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DeviceToken, XboxError>` - The device token, which can be reused until it expires.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`XboxError`] if signing fails or Xbox Live refuses the request.
+    pub async fn device_token(&self) -> Result<DeviceToken, XboxError> {
+        let body = json!({
+            "Properties": {
+                "AuthMethod": "ProofOfPossession",
+                "Id": self.device_id,
+                "DeviceType": "Android",
+                "Version": "10",
+                "ProofKey": self.key.jwk(),
+            },
+            "RelyingParty": "http://auth.xboxlive.com",
+            "TokenType": "JWT",
+        });
+        let url = format!("{}/device/authenticate", self.config.endpoints.xbox_device);
+        let output: DeviceOutput = send_json(self.signed(&url, &body)?).await?;
+
+        Ok(DeviceToken {
+            expires_at: xbox::timestamp(&output.not_after).ok_or_else(|| {
+                XboxError::ResponseError(format!("Invalid expiry time {}", output.not_after))
+            })?,
+            token: output.token,
+        })
+    }
+
+    /// Authorizes a user for a relying party in a single signed request.
+    ///
+    /// # Arguments
+    ///
+    /// * `access_token` - The Microsoft access token of the user.
+    /// * `kind` - Where the access token was issued, [`crate::TokenKind::Live`] for tokens from
+    ///   [`crate::LiveLogin`] and [`crate::TokenKind::Azure`] for tokens issued to an Azure app.
+    /// * `app_id` - The client ID the access token was issued to.
+    /// * `device_token` - A device token from [`Sisu::device_token`].
+    /// * `relying_party` - The service to authorize the user for, such as
+    ///   `https://pocket.realms.minecraft.net/` for Bedrock Realms or
+    ///   `https://multiplayer.minecraft.net/` for Bedrock servers.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BedrockAuthData, AuthError>` - The XSTS token for the relying party.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AuthError::Xbox`] if signing fails, or an [`AuthError::Xsts`] if SISU refuses the request.
+    pub async fn authorize(
+        &self,
+        access_token: &str,
+        kind: xbox::TokenKind,
+        app_id: &str,
+        device_token: &DeviceToken,
+        relying_party: &str,
+    ) -> Result<BedrockAuthData, AuthError> {
+        let body = json!({
+            "AccessToken": kind.rps_ticket(access_token),
+            "AppId": app_id,
+            "DeviceToken": device_token.token,
+            "Sandbox": "RETAIL",
+            "UseModernGamertag": true,
+            "SiteName": "user.auth.xboxlive.com",
+            "RelyingParty": relying_party,
+            "ProofKey": self.key.jwk(),
+        });
+        let url = format!("{}/authorize", self.config.endpoints.xbox_sisu);
+        let output: SisuOutput = send_json(self.signed(&url, &body)?)
+            .await
+            .map_err(XTSError::from)?;
+
+        Ok(BedrockAuthData::from_xsts(output.authorization_token)?)
+    }
+
+    /// Builds a POST request with a `Signature` header for `body`.
+    fn signed(&self, url: &str, body: &Value) -> Result<RequestBuilder, XboxError> {
+        let path = Url::parse(url).map_err(|err| XboxError::Signing(err.to_string()))?;
+        let body = serde_json::to_vec(body).map_err(|err| XboxError::Signing(err.to_string()))?;
+        let signature = self
+            .key
+            .sign("POST", &path[Position::BeforePath..], "", &body)?;
+
+        Ok(self
+            .config
+            .client
+            .post(url)
+            .header(ACCEPT, "application/json")
+            .header(CONTENT_TYPE, "application/json")
+            .header("x-xbl-contract-version", "1")
+            .header("Signature", signature)
+            .body(body))
+    }
+}
+
+/// Returns the current time as a Windows file time, which request signatures are timestamped with.
+fn filetime() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    u64::try_from(since_epoch.as_nanos() / 100)
+        .unwrap_or(u64::MAX)
+        .saturating_add(UNIX_EPOCH_FILETIME)
+}
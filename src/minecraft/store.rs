@@ -0,0 +1,400 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{errors::StoreError, AuthSession};
+
+/// The file format version written by this version of the crate.
+const FORMAT_VERSION: u32 = 1;
+/// The PBKDF2 iterations used for new passphrase protected stores.
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// The most PBKDF2 iterations a store may ask for, so a damaged file can't stall loading it.
+const MAX_PBKDF2_ITERATIONS: u32 = 10 * PBKDF2_ITERATIONS;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// The key an [`AccountStore`] is encrypted with.
+#[derive(Clone, PartialEq, Eq)]
+pub enum StoreKey {
+    /// A passphrase, stretched into a key with PBKDF2-HMAC-SHA256.
+    Passphrase(String),
+    /// A file holding a 256-bit key as hex, such as one made by [`AccountStore::generate_key_file`].
+    KeyFile(PathBuf),
+}
+
+impl fmt::Debug for StoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Passphrase(_) => f.debug_tuple("Passphrase").field(&"<redacted>").finish(),
+            Self::KeyFile(path) => f.debug_tuple("KeyFile").field(path).finish(),
+        }
+    }
+}
+
+/// The encrypted file as stored on disk.
+#[derive(Debug, Deserialize, Serialize)]
+struct Envelope {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<Kdf>,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// How the key of a passphrase protected store was derived.
+#[derive(Debug, Deserialize, Serialize)]
+struct Kdf {
+    algorithm: String,
+    iterations: u32,
+    salt: String,
+}
+
+/// The decrypted contents of the store.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct Contents {
+    pub(crate) sessions: Vec<AuthSession>,
+    /// The UUID of the account chosen in an [`crate::AccountManager`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) active: Option<String>,
+}
+
+/// A key stretched from the passphrase, kept so PBKDF2 only runs once per salt.
+struct DerivedKey {
+    iterations: u32,
+    salt: Vec<u8>,
+    key: [u8; KEY_LEN],
+}
+
+impl fmt::Debug for DerivedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DerivedKey")
+            .field("iterations", &self.iterations)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An encrypted file of saved sessions.
+///
+/// The sessions are serialized to JSON and encrypted with AES-256-GCM, keyed by either a passphrase
+/// or a key file. Every save writes a temporary file next to the store and renames it into place,
+/// so the store is never left half written. On Unix the file is only readable by its owner.
+///
+/// A key derived from a passphrase is kept in memory, and shared with clones of the store, so
+/// only the first read or write pays for PBKDF2.
+#[derive(Debug, Clone)]
+pub struct AccountStore {
+    path: PathBuf,
+    key: StoreKey,
+    derived: Arc<Mutex<Option<DerivedKey>>>,
+}
+
+impl AccountStore {
+    /// Opens the store at `path`, which doesn't need to exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where the store is saved.
+    /// * `key` - The key the store is encrypted with.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The store, nothing is read until [`AccountStore::load`] is called.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>, key: StoreKey) -> Self {
+        Self {
+            path: path.into(),
+            key,
+            derived: Arc::default(),
+        }
+    }
+
+    /// Returns where the store is saved.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads and decrypts the saved sessions.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<AuthSession>, StoreError>` - The saved sessions, which are empty if the store doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Decrypt`] if the key is wrong or the file was modified, or another
+    /// [`StoreError`] if the file can't be read or was written by a newer version.
+    pub fn load(&self) -> Result<Vec<AuthSession>, StoreError> {
+        Ok(self.read()?.sessions)
+    }
+
+    /// Encrypts and saves the sessions, replacing whatever sessions the store held before.
+    ///
+    /// The active account chosen in an [`crate::AccountManager`] is kept as long as its session is
+    /// still among `sessions`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sessions` - The sessions to save.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`StoreError`] if the key file can't be read or the store can't be written.
+    pub fn save(&self, sessions: &[AuthSession]) -> Result<(), StoreError> {
+        // A store that can't be read, for example after changing the key, is simply overwritten.
+        let active = self
+            .read()
+            .ok()
+            .and_then(|contents| contents.active)
+            .filter(|uuid| sessions.iter().any(|session| &session.uuid == uuid));
+
+        self.write(&Contents {
+            sessions: sessions.to_vec(),
+            active,
+        })
+    }
+
+    pub(crate) fn read(&self) -> Result<Contents, StoreError> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Contents::default())
+            }
+            Err(err) => return Err(StoreError::Io(err.to_string())),
+        };
+        let envelope: Envelope =
+            serde_json::from_slice(&data).map_err(|err| StoreError::Format(err.to_string()))?;
+
+        // Older format versions are migrated here as the format changes.
+        if envelope.version != FORMAT_VERSION {
+            return Err(StoreError::UnsupportedVersion(envelope.version));
+        }
+
+        let key = match (&self.key, &envelope.kdf) {
+            (StoreKey::Passphrase(passphrase), Some(kdf)) => {
+                if kdf.algorithm != "pbkdf2-sha256" {
+                    return Err(StoreError::Format(format!(
+                        "Unknown key derivation {}",
+                        kdf.algorithm
+                    )));
+                }
+                if kdf.iterations > MAX_PBKDF2_ITERATIONS {
+                    return Err(StoreError::Format(format!(
+                        "Too many PBKDF2 iterations {}",
+                        kdf.iterations
+                    )));
+                }
+                self.passphrase_key(passphrase, kdf.iterations, &decode(&kdf.salt)?)?
+            }
+            (StoreKey::KeyFile(path), None) => read_key_file(path)?,
+            (StoreKey::Passphrase(_), None) => {
+                return Err(StoreError::Key(
+                    "The store is protected by a key file, not a passphrase".to_string(),
+                ))
+            }
+            (StoreKey::KeyFile(_), Some(_)) => {
+                return Err(StoreError::Key(
+                    "The store is protected by a passphrase, not a key file".to_string(),
+                ))
+            }
+        };
+        let nonce: [u8; NONCE_LEN] = decode(&envelope.nonce)?
+            .try_into()
+            .map_err(|_| StoreError::Format("Invalid nonce".to_string()))?;
+        let mut ciphertext = decode(&envelope.ciphertext)?;
+
+        let plaintext = cipher(&key)?
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                aad(envelope.version),
+                &mut ciphertext,
+            )
+            .map_err(|_| StoreError::Decrypt)?;
+
+        serde_json::from_slice(plaintext).map_err(|err| StoreError::Format(err.to_string()))
+    }
+
+    pub(crate) fn write(&self, contents: &Contents) -> Result<(), StoreError> {
+        let rng = SystemRandom::new();
+        let (key, kdf) = match &self.key {
+            StoreKey::Passphrase(passphrase) => {
+                // The salt of the cached key is reused, a fresh nonce keeps every write unique.
+                let salt = match &*self.derived() {
+                    Some(derived) if derived.iterations == PBKDF2_ITERATIONS => {
+                        derived.salt.clone()
+                    }
+                    _ => random::<SALT_LEN>(&rng)?.to_vec(),
+                };
+                let kdf = Kdf {
+                    algorithm: "pbkdf2-sha256".to_string(),
+                    iterations: PBKDF2_ITERATIONS,
+                    salt: hex::encode(&salt),
+                };
+                (
+                    self.passphrase_key(passphrase, PBKDF2_ITERATIONS, &salt)?,
+                    Some(kdf),
+                )
+            }
+            StoreKey::KeyFile(path) => (read_key_file(path)?, None),
+        };
+
+        let mut data =
+            serde_json::to_vec(contents).map_err(|err| StoreError::Format(err.to_string()))?;
+        let nonce: [u8; NONCE_LEN] = random(&rng)?;
+        cipher(&key)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                aad(FORMAT_VERSION),
+                &mut data,
+            )
+            .map_err(|_| StoreError::Key("Encryption failed".to_string()))?;
+
+        let envelope = Envelope {
+            version: FORMAT_VERSION,
+            kdf,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(data),
+        };
+        let data = serde_json::to_vec_pretty(&envelope)
+            .map_err(|err| StoreError::Format(err.to_string()))?;
+
+        write_atomic(&self.path, &data)
+    }
+
+    /// Returns the key for `salt`, deriving it only if the cached key was made for another salt.
+    fn passphrase_key(
+        &self,
+        passphrase: &str,
+        iterations: u32,
+        salt: &[u8],
+    ) -> Result<[u8; KEY_LEN], StoreError> {
+        let mut derived = self.derived();
+        if let Some(derived) = derived
+            .as_ref()
+            .filter(|derived| derived.iterations == iterations && derived.salt == salt)
+        {
+            return Ok(derived.key);
+        }
+
+        let key = derive_key(passphrase, iterations, salt)?;
+        *derived = Some(DerivedKey {
+            iterations,
+            salt: salt.to_vec(),
+            key,
+        });
+
+        Ok(key)
+    }
+
+    fn derived(&self) -> std::sync::MutexGuard<'_, Option<DerivedKey>> {
+        self.derived.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes a new random key to `path`, for use with [`StoreKey::KeyFile`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to write the key, which must not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Io`] if the file already exists or can't be written.
+    pub fn generate_key_file(path: impl AsRef<Path>) -> Result<(), StoreError> {
+        let key: [u8; KEY_LEN] = random(&SystemRandom::new())?;
+        let mut file = private_file(OpenOptions::new().write(true).create_new(true))
+            .open(path)
+            .map_err(|err| StoreError::Io(err.to_string()))?;
+
+        file.write_all(hex::encode(key).as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(|err| StoreError::Io(err.to_string()))
+    }
+}
+
+/// The additional data every ciphertext is bound to, so the version can't be swapped.
+fn aad(version: u32) -> Aad<[u8; 4]> {
+    Aad::from(version.to_be_bytes())
+}
+
+fn cipher(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, StoreError> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| StoreError::Key("Invalid key length".to_string()))
+}
+
+fn derive_key(passphrase: &str, iterations: u32, salt: &[u8]) -> Result<[u8; KEY_LEN], StoreError> {
+    let iterations = NonZeroU32::new(iterations)
+        .ok_or_else(|| StoreError::Format("Invalid PBKDF2 iterations".to_string()))?;
+    let mut key = [0; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+
+    Ok(key)
+}
+
+fn read_key_file(path: &Path) -> Result<[u8; KEY_LEN], StoreError> {
+    let key = fs::read_to_string(path).map_err(|err| StoreError::Io(err.to_string()))?;
+
+    hex::decode(key.trim())
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| StoreError::Key("The key file must hold 32 bytes as hex".to_string()))
+}
+
+fn random<const N: usize>(rng: &SystemRandom) -> Result<[u8; N], StoreError> {
+    let mut bytes = [0; N];
+    rng.fill(&mut bytes)
+        .map_err(|_| StoreError::Key("No randomness available".to_string()))?;
+
+    Ok(bytes)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, StoreError> {
+    hex::decode(value).map_err(|err| StoreError::Format(err.to_string()))
+}
+
+/// Restricts a newly created file to its owner where the platform supports it.
+fn private_file(options: &mut OpenOptions) -> &mut OpenOptions {
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(options, 0o600);
+    options
+}
+
+/// Writes `data` to a temporary file next to `path` and renames it over `path`.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<(), StoreError> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let write = || -> std::io::Result<()> {
+        let mut file: File =
+            private_file(OpenOptions::new().write(true).create(true).truncate(true)).open(&temp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    };
+
+    write().map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        StoreError::Io(err.to_string())
+    })
+}
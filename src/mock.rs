@@ -0,0 +1,714 @@
+#![forbid(unsafe_code, missing_docs)]
+#![warn(clippy::pedantic)]
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{AuthConfig, Endpoints};
+
+/// A failure the [`MockServer`] can be told to answer with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The token endpoint rejects every code and refresh token with `invalid_grant`.
+    InvalidGrant,
+    /// The user declines the device code sign in.
+    DeviceDeclined,
+    /// The device code expires before the user signs in.
+    DeviceExpired,
+    /// Xbox Live user authentication answers `401 Unauthorized`.
+    XboxUnauthorized,
+    /// XSTS refuses the token with the given `XErr` code.
+    Xsts(u64),
+    /// `login_with_xbox` answers with the given status.
+    MinecraftLogin(u16),
+    /// The account has no entitlements, so it doesn't own Java Edition.
+    NotOwned,
+    /// The account has no Minecraft profile.
+    NoProfile,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    failure: Option<Failure>,
+    pending: u32,
+    slow_down: u32,
+    requests: Vec<String>,
+    joined: Option<String>,
+}
+
+/// A local stand-in for the Microsoft, `login.live.com`, Xbox Live and Minecraft services endpoints.
+///
+/// It also serves a Yggdrasil auth server under `/yggdrasil`, which every response points at with
+/// the `ALI` header. The server answers on `127.0.0.1` like the real services would for a single account, whose
+/// tokens and profile are the associated constants below. Point a sign in at it with
+/// [`MockServer::config`], and use [`MockServer::fail`] to test how errors are handled.
+///
+/// The server stops when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// The authorization code the token endpoint accepts.
+    pub const CODE: &'static str = "mock-code";
+    /// The client secret the token endpoint accepts, if one is sent, with characters that need encoding.
+    pub const CLIENT_SECRET: &'static str = "mock&secret+=";
+    /// The device code handed out by the device code endpoint.
+    pub const DEVICE_CODE: &'static str = "mock-device-code";
+    /// The user code handed out by the device code endpoint.
+    pub const USER_CODE: &'static str = "MOCK1234";
+    /// The Microsoft access token.
+    pub const MICROSOFT_TOKEN: &'static str = "mock-microsoft-token";
+    /// The access token issued by `login.live.com`.
+    pub const LIVE_TOKEN: &'static str = "mock-live-token";
+    /// The Microsoft refresh token.
+    pub const REFRESH_TOKEN: &'static str = "mock-refresh-token";
+    /// The Xbox Live user token.
+    pub const XBOX_TOKEN: &'static str = "mock-xbox-token";
+    /// The Xbox Live device token.
+    pub const DEVICE_TOKEN: &'static str = "mock-device-token";
+    /// The XSTS token.
+    pub const XSTS_TOKEN: &'static str = "mock-xsts-token";
+    /// The Xbox Live user hash.
+    pub const USER_HASH: &'static str = "1234567890";
+    /// The Xbox Live user ID.
+    pub const XUID: &'static str = "2535400000000000";
+    /// The Xbox Live gamertag.
+    pub const GAMERTAG: &'static str = "MockGamer";
+    /// The Minecraft access token.
+    pub const MINECRAFT_TOKEN: &'static str = "mock-minecraft-token";
+    /// The player's UUID.
+    pub const PROFILE_ID: &'static str = "069a79f444e94726a5befca90e38aaf5";
+    /// The player's name.
+    pub const PROFILE_NAME: &'static str = "MockPlayer";
+    /// The username the Yggdrasil auth server accepts.
+    pub const USERNAME: &'static str = "mock@example.com";
+    /// The password the Yggdrasil auth server accepts.
+    pub const PASSWORD: &'static str = "mock-password";
+    /// The access token issued by the Yggdrasil auth server.
+    pub const YGGDRASIL_TOKEN: &'static str = "mock-yggdrasil-token";
+
+    /// Starts the server on a free port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no port could be bound.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let task = tokio::spawn(serve(listener, addr, Arc::clone(&state)));
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// Returns the base URL of the server, such as `http://127.0.0.1:49152`.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns a config pointing every endpoint at the server.
+    #[must_use]
+    pub fn config(&self) -> AuthConfig {
+        AuthConfig::new().endpoints(Endpoints::all(&self.url()))
+    }
+
+    /// Makes the server answer with the given failure, or succeed again with `None`.
+    pub fn fail(&self, failure: Option<Failure>) {
+        self.state().failure = failure;
+    }
+
+    /// Answers the given number of device code polls with `authorization_pending`.
+    pub fn pending(&self, polls: u32) {
+        self.state().pending = polls;
+    }
+
+    /// Answers the given number of device code polls with `slow_down`.
+    pub fn slow_down(&self, polls: u32) {
+        self.state().slow_down = polls;
+    }
+
+    /// Returns the requests received so far, such as `POST /user/authenticate`.
+    #[must_use]
+    pub fn requests(&self) -> Vec<String> {
+        self.state().requests.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, addr: SocketAddr, state: Arc<Mutex<State>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(connection(stream, addr, Arc::clone(&state)));
+    }
+}
+
+async fn connection(mut stream: TcpStream, addr: SocketAddr, state: Arc<Mutex<State>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    let (status, body) = {
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        state
+            .requests
+            .push(format!("{} {}", request.method, request.path));
+        route(&request, addr, &mut state)
+    };
+
+    let body = if status == 204 {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let response = format!(
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nX-Authlib-Injector-API-Location: /yggdrasil/\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    authorization: Option<String>,
+    signature: Option<String>,
+    body: String,
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let header_end = loop {
+        let mut chunk = [0; 1024];
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    let mut authorization = None;
+    let mut signature = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse().ok()?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("signature") {
+            signature = Some(value.trim().to_string());
+        }
+    }
+
+    while buffer.len() < header_end + content_length {
+        let mut chunk = [0; 1024];
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        authorization,
+        signature,
+        body: String::from_utf8_lossy(&buffer[header_end..header_end + content_length])
+            .into_owned(),
+    })
+}
+
+fn route(request: &Request, addr: SocketAddr, state: &mut State) -> (u16, Value) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/consumers/oauth2/v2.0/devicecode") => devicecode(request, addr),
+        ("POST", path) if path.ends_with("/oauth2/v2.0/token") => token(request, state),
+        ("POST", "/oauth20_token.srf") => live_token(request, state.failure),
+        ("POST", "/user/authenticate") => xbl(request, state.failure),
+        ("POST", "/xsts/authorize") => xsts(request, state.failure),
+        ("POST", "/device/authenticate") => device_authenticate(request),
+        ("POST", "/authorize") => sisu_authorize(request),
+        ("POST", "/authentication/login_with_xbox") => login_with_xbox(request, state.failure),
+        ("GET", "/entitlements/mcstore") => entitlements(request, state.failure),
+        ("GET", "/minecraft/profile") => profile(request, state.failure),
+        ("POST", "/session/minecraft/join") => join(request, state),
+        ("GET", "/session/minecraft/hasJoined") => has_joined(request, state),
+        ("POST", "/yggdrasil/authserver/authenticate") => yggdrasil_authenticate(request),
+        ("POST", "/yggdrasil/authserver/refresh") => yggdrasil_refresh(request),
+        ("POST", "/yggdrasil/authserver/validate" | "/yggdrasil/authserver/invalidate") => {
+            yggdrasil_validate(request)
+        }
+        ("POST", "/yggdrasil/authserver/signout") => yggdrasil_signout(request),
+        _ => (404, json!({ "error": "NOT_FOUND" })),
+    }
+}
+
+fn devicecode(request: &Request, addr: SocketAddr) -> (u16, Value) {
+    let form: HashMap<String, String> =
+        serde_urlencoded::from_str(&request.body).unwrap_or_default();
+    if form.get("scope").map(String::as_str) != Some("XboxLive.signin XboxLive.offline_access") {
+        return oauth_error("invalid_scope");
+    }
+
+    (
+        200,
+        json!({
+            "device_code": MockServer::DEVICE_CODE,
+            "user_code": MockServer::USER_CODE,
+            "verification_uri": format!("http://{addr}/devicelogin"),
+            "expires_in": 900,
+            "interval": 0,
+            "message": format!("To sign in, visit http://{addr}/devicelogin and enter the code {}.", MockServer::USER_CODE),
+        }),
+    )
+}
+
+fn oauth_error(error: &str) -> (u16, Value) {
+    (
+        400,
+        json!({ "error": error, "error_description": format!("Mock {error}") }),
+    )
+}
+
+fn token(request: &Request, state: &mut State) -> (u16, Value) {
+    let form: HashMap<String, String> =
+        serde_urlencoded::from_str(&request.body).unwrap_or_default();
+    let field = |name: &str| form.get(name).map(String::as_str);
+
+    let accepted = match field("grant_type") {
+        Some("authorization_code") => {
+            field("code") == Some(MockServer::CODE) && field("code_verifier").is_some()
+        }
+        Some("refresh_token") => field("refresh_token") == Some(MockServer::REFRESH_TOKEN),
+        Some("urn:ietf:params:oauth:grant-type:device_code") => {
+            if field("device_code") != Some(MockServer::DEVICE_CODE) {
+                return oauth_error("bad_verification_code");
+            }
+            match state.failure {
+                Some(Failure::DeviceDeclined) => return oauth_error("authorization_declined"),
+                Some(Failure::DeviceExpired) => return oauth_error("expired_token"),
+                _ if state.slow_down > 0 => {
+                    state.slow_down -= 1;
+                    return oauth_error("slow_down");
+                }
+                _ if state.pending > 0 => {
+                    state.pending -= 1;
+                    return oauth_error("authorization_pending");
+                }
+                _ => true,
+            }
+        }
+        _ => return oauth_error("unsupported_grant_type"),
+    };
+    if field("client_secret").is_some_and(|secret| secret != MockServer::CLIENT_SECRET) {
+        return oauth_error("invalid_client");
+    }
+    if !accepted || state.failure == Some(Failure::InvalidGrant) {
+        return oauth_error("invalid_grant");
+    }
+
+    (
+        200,
+        json!({
+            "token_type": "Bearer",
+            "scope": "XboxLive.signin XboxLive.offline_access",
+            "expires_in": 3600,
+            "ext_expires_in": 3600,
+            "access_token": MockServer::MICROSOFT_TOKEN,
+            "refresh_token": MockServer::REFRESH_TOKEN,
+        }),
+    )
+}
+
+fn live_token(request: &Request, failure: Option<Failure>) -> (u16, Value) {
+    let form: HashMap<String, String> =
+        serde_urlencoded::from_str(&request.body).unwrap_or_default();
+    let field = |name: &str| form.get(name).map(String::as_str);
+
+    let accepted = match field("grant_type") {
+        Some("authorization_code") => field("code") == Some(MockServer::CODE),
+        Some("refresh_token") => field("refresh_token") == Some(MockServer::REFRESH_TOKEN),
+        _ => return oauth_error("unsupported_grant_type"),
+    };
+    if !accepted
+        || failure == Some(Failure::InvalidGrant)
+        || field("scope") != Some("service::user.auth.xboxlive.com::MBI_SSL")
+    {
+        return oauth_error("invalid_grant");
+    }
+
+    (
+        200,
+        json!({
+            "token_type": "bearer",
+            "expires_in": 86_400,
+            "scope": "service::user.auth.xboxlive.com::MBI_SSL",
+            "access_token": MockServer::LIVE_TOKEN,
+            "refresh_token": MockServer::REFRESH_TOKEN,
+            "user_id": "0000000000000000",
+        }),
+    )
+}
+
+fn xbox_token(token: &str, xui: &Value) -> Value {
+    json!({
+        "IssueInstant": "2024-05-02T04:09:16.6393548Z",
+        "NotAfter": "2124-05-02T20:09:16.6393548Z",
+        "Token": token,
+        "DisplayClaims": { "xui": [xui] },
+    })
+}
+
+fn xbl(request: &Request, failure: Option<Failure>) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    // Tokens from an Azure app and from `login.live.com` are passed with different prefixes.
+    if failure == Some(Failure::XboxUnauthorized) || !rps_ticket(&body["Properties"]["RpsTicket"]) {
+        return (401, Value::Null);
+    }
+
+    (
+        200,
+        xbox_token(
+            MockServer::XBOX_TOKEN,
+            &json!({ "uhs": MockServer::USER_HASH }),
+        ),
+    )
+}
+
+/// Checks that a Microsoft access token was passed with the prefix of where it was issued.
+fn rps_ticket(ticket: &Value) -> bool {
+    let ticket = ticket.as_str();
+    ticket == Some(&format!("d={}", MockServer::MICROSOFT_TOKEN))
+        || ticket == Some(&format!("t={}", MockServer::LIVE_TOKEN))
+}
+
+fn xsts(request: &Request, failure: Option<Failure>) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if body["Properties"]["UserTokens"][0] != MockServer::XBOX_TOKEN {
+        return (401, Value::Null);
+    }
+    if let Some(Failure::Xsts(code)) = failure {
+        return (
+            401,
+            json!({
+                "Identity": "0",
+                "XErr": code,
+                "Message": "",
+                "Redirect": "https://start.ui.xboxlive.com/CreateAccount",
+            }),
+        );
+    }
+
+    (
+        200,
+        xbox_token(MockServer::XSTS_TOKEN, &xui(&body["RelyingParty"])),
+    )
+}
+
+/// The display claims XSTS includes for a relying party.
+fn xui(relying_party: &Value) -> Value {
+    // Only Xbox Live itself is told everything about the user.
+    match relying_party.as_str() {
+        Some("rp://api.minecraftservices.com/") => json!({ "uhs": MockServer::USER_HASH }),
+        Some("http://xboxlive.com") => json!({
+            "uhs": MockServer::USER_HASH,
+            "xid": MockServer::XUID,
+            "gtg": MockServer::GAMERTAG,
+            "agg": "Adult",
+            "prv": "185 186 187 188 191 192 193 196 198 199 200 201 203 204 205 206 207 208 211 214 215 216 217 220 224 227 228 235 238 245 247 249 252 254 255",
+        }),
+        _ => json!({
+            "uhs": MockServer::USER_HASH,
+            "xid": MockServer::XUID,
+            "gtg": MockServer::GAMERTAG,
+        }),
+    }
+}
+
+/// Checks the `Signature` header of a request against the public key in its `ProofKey`.
+fn signed(request: &Request, body: &Value) -> bool {
+    let Some(header) = request
+        .signature
+        .as_deref()
+        .and_then(|signature| STANDARD.decode(signature).ok())
+        .filter(|header| header.len() == 76)
+    else {
+        return false;
+    };
+    let coordinate = |name: &str| {
+        body["ProofKey"][name]
+            .as_str()
+            .and_then(|value| URL_SAFE_NO_PAD.decode(value).ok())
+            .unwrap_or_default()
+    };
+    let public_key = [vec![4], coordinate("x"), coordinate("y")].concat();
+
+    let mut data = Vec::new();
+    for part in [
+        &header[..4],
+        &header[4..12],
+        request.method.as_bytes(),
+        request.path.as_bytes(),
+        request
+            .authorization
+            .as_deref()
+            .unwrap_or_default()
+            .as_bytes(),
+        request.body.as_bytes(),
+    ] {
+        data.extend_from_slice(part);
+        data.push(0);
+    }
+
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, public_key)
+        .verify(&data, &header[12..])
+        .is_ok()
+}
+
+fn device_authenticate(request: &Request) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if body["Properties"]["AuthMethod"] != "ProofOfPossession"
+        || !signed(request, &body["Properties"])
+    {
+        return (401, Value::Null);
+    }
+
+    (
+        200,
+        json!({
+            "IssueInstant": "2024-05-02T04:09:16.6393548Z",
+            "NotAfter": "2124-05-02T20:09:16.6393548Z",
+            "Token": MockServer::DEVICE_TOKEN,
+            "DisplayClaims": { "xdi": { "did": "F000000000000000", "dcs": "0" } },
+        }),
+    )
+}
+
+fn sisu_authorize(request: &Request) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if !rps_ticket(&body["AccessToken"])
+        || body["DeviceToken"] != MockServer::DEVICE_TOKEN
+        || !signed(request, &body)
+    {
+        return (400, Value::Null);
+    }
+
+    (
+        200,
+        json!({
+            "DeviceToken": MockServer::DEVICE_TOKEN,
+            "UserToken": xbox_token(MockServer::XBOX_TOKEN, &json!({ "uhs": MockServer::USER_HASH })),
+            "AuthorizationToken": xbox_token(MockServer::XSTS_TOKEN, &xui(&body["RelyingParty"])),
+            "Sandbox": "RETAIL",
+            "UseModernGamertag": true,
+        }),
+    )
+}
+
+fn login_with_xbox(request: &Request, failure: Option<Failure>) -> (u16, Value) {
+    if let Some(Failure::MinecraftLogin(status)) = failure {
+        return (
+            status,
+            json!({ "path": "/authentication/login_with_xbox", "error": "Mock" }),
+        );
+    }
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    let identity_token = format!(
+        "XBL3.0 x={};{}",
+        MockServer::USER_HASH,
+        MockServer::XSTS_TOKEN
+    );
+    if body["identityToken"] != *identity_token {
+        return (401, json!({ "error": "UNAUTHORIZED" }));
+    }
+
+    (
+        200,
+        json!({
+            "username": "00000000-0000-0000-0000-000000000000",
+            "roles": [],
+            "access_token": MockServer::MINECRAFT_TOKEN,
+            "token_type": "Bearer",
+            "expires_in": 86_400,
+        }),
+    )
+}
+
+fn authorized(request: &Request) -> bool {
+    request.authorization.as_deref()
+        == Some(format!("Bearer {}", MockServer::MINECRAFT_TOKEN).as_str())
+}
+
+fn entitlements(request: &Request, failure: Option<Failure>) -> (u16, Value) {
+    if !authorized(request) {
+        return (401, Value::Null);
+    }
+    let items = if failure == Some(Failure::NotOwned) {
+        json!([])
+    } else {
+        json!([
+            { "name": "product_minecraft", "signature": "mock" },
+            { "name": "game_minecraft", "signature": "mock" },
+        ])
+    };
+
+    (
+        200,
+        json!({ "items": items, "signature": "mock", "keyId": "1" }),
+    )
+}
+
+fn profile(request: &Request, failure: Option<Failure>) -> (u16, Value) {
+    if !authorized(request) {
+        return (401, Value::Null);
+    }
+    if failure == Some(Failure::NoProfile) {
+        return (
+            404,
+            json!({ "path": "/minecraft/profile", "error": "NOT_FOUND" }),
+        );
+    }
+
+    (
+        200,
+        json!({
+            "id": MockServer::PROFILE_ID,
+            "name": MockServer::PROFILE_NAME,
+            "skins": [],
+            "capes": [],
+        }),
+    )
+}
+
+fn join(request: &Request, state: &mut State) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if body["accessToken"] != MockServer::MINECRAFT_TOKEN
+        || body["selectedProfile"] != MockServer::PROFILE_ID
+    {
+        return (
+            403,
+            json!({ "error": "ForbiddenOperationException", "errorMessage": "Invalid token." }),
+        );
+    }
+    state.joined = body["serverId"].as_str().map(str::to_string);
+
+    (204, Value::Null)
+}
+
+fn has_joined(request: &Request, state: &State) -> (u16, Value) {
+    let query: HashMap<String, String> =
+        serde_urlencoded::from_str(&request.query).unwrap_or_default();
+    if query.get("username").map(String::as_str) != Some(MockServer::PROFILE_NAME)
+        || query.get("serverId") != state.joined.as_ref()
+    {
+        return (204, Value::Null);
+    }
+
+    (
+        200,
+        json!({
+            "id": MockServer::PROFILE_ID,
+            "name": MockServer::PROFILE_NAME,
+            "properties": [{ "name": "textures", "value": "e30=", "signature": "mock" }],
+        }),
+    )
+}
+
+fn yggdrasil_error(status: u16, message: &str) -> (u16, Value) {
+    (
+        status,
+        json!({ "error": "ForbiddenOperationException", "errorMessage": message }),
+    )
+}
+
+fn yggdrasil_session(body: &Value) -> (u16, Value) {
+    (
+        200,
+        json!({
+            "accessToken": MockServer::YGGDRASIL_TOKEN,
+            "clientToken": body["clientToken"],
+            "availableProfiles": [{ "id": MockServer::PROFILE_ID, "name": MockServer::PROFILE_NAME }],
+            "selectedProfile": { "id": MockServer::PROFILE_ID, "name": MockServer::PROFILE_NAME },
+        }),
+    )
+}
+
+fn yggdrasil_credentials(body: &Value) -> bool {
+    body["username"] == MockServer::USERNAME && body["password"] == MockServer::PASSWORD
+}
+
+fn yggdrasil_authenticate(request: &Request) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if !yggdrasil_credentials(&body) {
+        return yggdrasil_error(403, "Invalid credentials. Invalid username or password.");
+    }
+
+    yggdrasil_session(&body)
+}
+
+fn yggdrasil_refresh(request: &Request) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if body["accessToken"] != MockServer::YGGDRASIL_TOKEN {
+        return yggdrasil_error(403, "Invalid token.");
+    }
+
+    yggdrasil_session(&body)
+}
+
+fn yggdrasil_validate(request: &Request) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if body["accessToken"] != MockServer::YGGDRASIL_TOKEN {
+        return yggdrasil_error(403, "Invalid token.");
+    }
+
+    (204, Value::Null)
+}
+
+fn yggdrasil_signout(request: &Request) -> (u16, Value) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if !yggdrasil_credentials(&body) {
+        return yggdrasil_error(403, "Invalid credentials. Invalid username or password.");
+    }
+
+    (204, Value::Null)
+}
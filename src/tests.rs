@@ -1,34 +1,298 @@
 use super::*;
+#[cfg(feature = "custom-auth")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use dotenv::dotenv;
-use std::env;
 
 #[cfg(feature = "custom-auth")]
-#[tokio::test]
-async fn test_oauth_url() {
-    let _ = dotenv();
-    let client_id = env::var("Client_ID").expect("Expected Client ID");
-    let oauth = Oauth::new(&client_id, None);
-    let params = format!("client_id={}&response_type=code&redirect_uri=http://localhost:8000&response_mode=query&scope={}&state=12345", client_id, SCOPE);
+#[test]
+fn test_oauth_url() {
+    let client_id = "client";
+    let oauth = Oauth::new(client_id, None);
+    let code_challenge = URL_SAFE_NO_PAD.encode(ring::digest::digest(
+        &ring::digest::SHA256,
+        oauth.code_verifier.as_bytes(),
+    ));
+    let params = format!("client_id={client_id}&response_type=code&redirect_uri=http://localhost:8000&response_mode=query&scope={SCOPE}&state={}&code_challenge={code_challenge}&code_challenge_method=S256", oauth.state);
     let expected_url = format!(
         "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize/?{}",
         params
     );
     assert_eq!(oauth.url(), expected_url);
+    assert_ne!(oauth.state, Oauth::new(client_id, None).state);
+}
+
+#[cfg(feature = "custom-auth")]
+async fn mock_oauth_launch(server: &mock::MockServer) -> Result<CustomAuthData, errors::AuthError> {
+    let oauth = Oauth::bind("client", RedirectPort::Ephemeral)
+        .unwrap()
+        .config(server.config().xbox_identity(true));
+    let callback = format!(
+        "http://127.0.0.1:{}/?code={}&state={}",
+        oauth.port(),
+        mock::MockServer::CODE,
+        oauth.state
+    );
+    let browser = tokio::spawn(reqwest::get(callback));
+    let result = oauth.launch(None).await;
+    assert!(browser.await.unwrap().unwrap().status().is_success());
+    result
 }
 
 #[cfg(feature = "custom-auth")]
 #[tokio::test]
-async fn test_device_code_prelaunch() {
-    let _ = dotenv();
-    let client_id = env::var("Client_ID").expect("Expected Client ID.");
-    let device_code = DeviceCode::new(&client_id).await.unwrap();
+async fn test_oauth_launch_mock() {
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let auth = mock_oauth_launch(&server).await.unwrap();
+    assert_eq!(auth.access_token, MockServer::MINECRAFT_TOKEN);
+    assert_eq!(auth.uuid, MockServer::PROFILE_ID);
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+    assert!(auth.entitlements.owns_java());
+    let identity = auth.xbox_identity.unwrap();
+    assert_eq!(identity.xuid, MockServer::XUID);
+    assert_eq!(identity.gamertag, MockServer::GAMERTAG);
+    assert_eq!(identity.age_group, AgeGroup::Adult);
+    assert!(identity.has_privilege(254));
+    assert!(!identity.has_privilege(1));
+
+    let oauth = Oauth::new("client", None).config(server.config());
+    let xsts_requests = || {
+        server
+            .requests()
+            .iter()
+            .filter(|request| *request == "POST /xsts/authorize")
+            .count()
+    };
+    let before = xsts_requests();
+    let (auth, refresh_token) = oauth
+        .refresh(MockServer::REFRESH_TOKEN, None)
+        .await
+        .unwrap();
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+    assert_eq!(refresh_token, MockServer::REFRESH_TOKEN);
+    // Without asking for the identity only the Minecraft XSTS token is requested.
+    assert!(auth.xbox_identity.is_none());
+    assert_eq!(xsts_requests(), before + 1);
+    assert!(!server
+        .requests()
+        .contains(&"POST /consumers/oauth2/v2.0/devicecode".to_string()));
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_oauth_launch_mock_failures() {
+    use errors::{AuthError, AuthStage, MojangError, XTSError};
+    use mock::{Failure, MockServer};
+
+    let server = MockServer::start().await.unwrap();
+
+    server.fail(Some(Failure::InvalidGrant));
+    let error = mock_oauth_launch(&server).await.unwrap_err();
+    assert_eq!(
+        (error.stage(), error.status()),
+        (AuthStage::Token, Some(400))
+    );
+
+    server.fail(Some(Failure::XboxUnauthorized));
+    let error = mock_oauth_launch(&server).await.unwrap_err();
+    assert_eq!(
+        (error.stage(), error.status()),
+        (AuthStage::Xbox, Some(401))
+    );
+
+    server.fail(Some(Failure::Xsts(2_148_916_233)));
+    let error = mock_oauth_launch(&server).await.unwrap_err();
+    assert!(matches!(error, AuthError::Xsts(XTSError::NoXboxProfile)));
+
+    server.fail(Some(Failure::MinecraftLogin(429)));
+    let error = mock_oauth_launch(&server).await.unwrap_err();
+    assert_eq!(
+        (error.stage(), error.status()),
+        (AuthStage::Mojang, Some(429))
+    );
+
+    server.fail(Some(Failure::NotOwned));
+    let error = mock_oauth_launch(&server).await.unwrap_err();
+    assert!(matches!(
+        error,
+        AuthError::Mojang(MojangError::NotOwned { access_token, expires_in: 86_400 })
+            if access_token == MockServer::MINECRAFT_TOKEN
+    ));
+
+    server.fail(Some(Failure::NoProfile));
+    let error = mock_oauth_launch(&server).await.unwrap_err();
+    assert!(matches!(error, AuthError::Mojang(MojangError::NoProfile)));
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_oauth_launch_from_redirect_mock() {
+    use errors::{AuthError, OAuthError};
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let oauth = Oauth::new("client", None).config(server.config());
+
+    let redirect = format!(
+        "http://localhost:8000/?code={}&state={}",
+        MockServer::CODE,
+        oauth.state
+    );
+    let auth = oauth.launch_from_redirect(&redirect, None).await.unwrap();
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+
+    let query = format!("code={}&state={}", MockServer::CODE, oauth.state);
+    let auth = oauth.launch_from_redirect(&query, None).await.unwrap();
+    assert_eq!(auth.uuid, MockServer::PROFILE_ID);
+
+    let auth = oauth
+        .launch_from_redirect(&query, Some(MockServer::CLIENT_SECRET))
+        .await
+        .unwrap();
+    assert_eq!(auth.uuid, MockServer::PROFILE_ID);
+    let (_, refresh_token) = oauth
+        .refresh(MockServer::REFRESH_TOKEN, Some(MockServer::CLIENT_SECRET))
+        .await
+        .unwrap();
+    assert_eq!(refresh_token, MockServer::REFRESH_TOKEN);
+
+    let forged = format!("?code={}&state=forged", MockServer::CODE);
+    assert!(matches!(
+        oauth.launch_from_redirect(&forged, None).await,
+        Err(AuthError::OAuth(OAuthError::StateMismatch))
+    ));
+
+    let denied = format!(
+        "http://localhost:8000/?error=access_denied&error_description=Denied&state={}",
+        oauth.state
+    );
+    assert!(matches!(
+        oauth.launch_from_redirect(&denied, None).await,
+        Err(AuthError::OAuth(OAuthError::AuthenticationFailure(description))) if description == "Denied"
+    ));
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_oauth_builder_mock() {
+    use mock::MockServer;
+
+    let oauth = Oauth::builder("client")
+        .port(8080)
+        .tenant("common")
+        .scope("openid")
+        .prompt(Prompt::SelectAccount)
+        .login_hint("steve@example.com")
+        .domain_hint("consumers")
+        .redirect_path("callback")
+        .build();
+    assert_eq!(oauth.redirect_uri(), "http://localhost:8080/callback");
+    assert!(oauth
+        .url()
+        .starts_with("https://login.microsoftonline.com/common/oauth2/v2.0/authorize/?"));
+    assert!(oauth
+        .url()
+        .contains("&redirect_uri=http://localhost:8080/callback&"));
+    assert!(oauth.url().contains(&format!("&scope={SCOPE}%20openid&")));
+    assert!(oauth
+        .url()
+        .ends_with("&prompt=select_account&login_hint=steve%40example.com&domain_hint=consumers"));
+
+    let server = MockServer::start().await.unwrap();
+    let oauth = Oauth::builder("client")
+        .tenant("common")
+        .redirect_path("/callback")
+        .bind(RedirectPort::Ephemeral)
+        .unwrap()
+        .config(server.config());
+    let callback = format!(
+        "http://127.0.0.1:{}/callback?code={}&state={}",
+        oauth.port(),
+        MockServer::CODE,
+        oauth.state
+    );
+    let browser = tokio::spawn(async move {
+        let wrong_path = reqwest::get(callback.replace("/callback", "/")).await?;
+        let callback = reqwest::get(callback).await?;
+        Ok::<_, reqwest::Error>((wrong_path.status(), callback.status()))
+    });
+    let auth = oauth.launch(None).await.unwrap();
+    let (wrong_path, callback) = browser.await.unwrap().unwrap();
+    assert_eq!(wrong_path, 404);
+    assert!(callback.is_success());
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+    assert!(server
+        .requests()
+        .contains(&"POST /common/oauth2/v2.0/token".to_string()));
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_device_code_launch_mock() {
+    use errors::{AuthError, DeviceCodeError};
+    use mock::{Failure, MockServer};
+
+    let server = MockServer::start().await.unwrap();
+    let device_code = DeviceCode::with_config("client", server.config())
+        .await
+        .unwrap();
 
     let (url, message, expires_in, user_code) = device_code.preinfo();
+    assert_eq!(url, format!("{}/devicelogin", server.url()));
+    assert!(message.contains(MockServer::USER_CODE));
+    assert_eq!(expires_in, 900);
+    assert_eq!(user_code, MockServer::USER_CODE);
+
+    server.pending(2);
+    let session = device_code.launch_session().await.unwrap();
+    assert_eq!(session.profile.name, MockServer::PROFILE_NAME);
+    assert_eq!(session.refresh_token, MockServer::REFRESH_TOKEN);
+    let polls = server
+        .requests()
+        .iter()
+        .filter(|request| *request == "POST /consumers/oauth2/v2.0/token")
+        .count();
+    assert_eq!(polls, 3);
+
+    let session = device_code.refresh_session(&session).await.unwrap();
+    assert_eq!(session.access_token, MockServer::MINECRAFT_TOKEN);
 
-    assert_eq!(url, device_code.url);
-    assert_eq!(message, device_code.message);
-    assert_eq!(expires_in, device_code.expires_in);
-    assert_eq!(user_code, device_code.user_code);
+    server.fail(Some(Failure::DeviceDeclined));
+    assert!(matches!(
+        device_code.launch().await,
+        Err(AuthError::DeviceCode(DeviceCodeError::Declined))
+    ));
+
+    server.fail(Some(Failure::DeviceExpired));
+    assert!(matches!(
+        device_code.launch().await,
+        Err(AuthError::DeviceCode(DeviceCodeError::Expired))
+    ));
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_device_code_slow_down_mock() {
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let device_code = DeviceCode::with_config("client", server.config())
+        .await
+        .unwrap();
+
+    server.slow_down(1);
+    let started = std::time::Instant::now();
+    let auth = device_code.launch().await.unwrap();
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+    // `slow_down` adds five seconds to the polling interval.
+    assert!(started.elapsed() >= std::time::Duration::from_secs(5));
+    let polls = server
+        .requests()
+        .iter()
+        .filter(|request| *request == "POST /consumers/oauth2/v2.0/token")
+        .count();
+    assert_eq!(polls, 2);
 }
 
 #[cfg(feature = "custom-launch")]
@@ -50,3 +314,738 @@ async fn test_custom_launch() {
     assert_eq!(java_exe, launch_java_exe);
     assert_eq!(jre, launch_jre.clone());
 }
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_xbox_timestamp() {
+    assert_eq!(
+        xbox::timestamp("2024-05-02T20:09:16.6393548Z"),
+        Some(1_714_680_556)
+    );
+    assert_eq!(xbox::timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(xbox::timestamp("not a timestamp"), None);
+    assert_eq!(xbox::timestamp("2024-05-02T24:00:00Z"), None);
+    assert_eq!(xbox::timestamp("2024-05-02T20:60:00Z"), None);
+    assert_eq!(xbox::timestamp("2024-05-02T20:09:61Z"), None);
+    assert_eq!(
+        xbox::timestamp("18446744073709551615-05-02T20:09:16Z"),
+        None
+    );
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_session_expiry() {
+    let now = session::now();
+    let session = AuthSession {
+        access_token: "token".to_string(),
+        uuid: "uuid".to_string(),
+        profile: MinecraftProfile::default(),
+        xbox_identity: None,
+        entitlements: Entitlements::default(),
+        refresh_token: "refresh".to_string(),
+        token_kind: xbox::TokenKind::Azure,
+        tenant: "consumers".to_string(),
+        scope: "XboxLive.signin XboxLive.offline_access".to_string(),
+        msa_expires_at: now + 3_600,
+        xbl_expires_at: now + 3_600,
+        xsts_expires_at: now + 3_600,
+        minecraft_expires_at: now + 600,
+    };
+
+    assert!(!session.is_expired());
+    assert!(!session.needs_refresh(std::time::Duration::from_secs(60)));
+    assert!(session.needs_refresh(std::time::Duration::from_secs(900)));
+
+    let json = serde_json::to_string(&session).unwrap();
+    assert_eq!(serde_json::from_str::<AuthSession>(&json).unwrap(), session);
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_oauth_server_response() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn request(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    // Connections queue on the bound listener until the server accepts them.
+    let listener = RedirectPort::Ephemeral.bind().unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = oauth::server(
+        oauth::Listener::Bound(listener),
+        "expected".to_string(),
+        "/".to_string(),
+        CallbackPage::Html("done".to_string()),
+        CallbackPage::default_failure(),
+        None,
+    );
+    let server = tokio::spawn(server);
+
+    assert!(request(addr, "/favicon.ico")
+        .await
+        .starts_with("HTTP/1.1 404"));
+    let response = request(addr, "/?code=abc&state=expected").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("\r\n\r\ndone"));
+
+    let info = server.await.unwrap().unwrap();
+    assert_eq!(info.code.as_deref(), Some("abc"));
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_oauth_request_line() {
+    let line = oauth::parse_request_line("GET /?code=abc&state=x%20y HTTP/1.1\r\nHost: a\r\n\r\n")
+        .unwrap();
+    assert_eq!(line.path, "/");
+    let info = oauth::parse_info(line.query.unwrap()).unwrap();
+    assert_eq!(info.code.as_deref(), Some("abc"));
+    assert_eq!(info.state.as_deref(), Some("x y"));
+
+    let line = oauth::parse_request_line("GET /favicon.ico HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!((line.path, line.query), ("/favicon.ico", None));
+
+    assert!(oauth::parse_request_line("garbage\r\n\r\n").is_err());
+    assert!(oauth::parse_request_line("").is_err());
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_oauth_server_timeout() {
+    let listener = RedirectPort::Ephemeral.bind().unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let result = oauth::server(
+        oauth::Listener::Bound(listener),
+        "expected".to_string(),
+        "/".to_string(),
+        CallbackPage::default_success(),
+        CallbackPage::default_failure(),
+        Some(std::time::Duration::from_millis(50)),
+    )
+    .await;
+    assert!(matches!(result, Err(errors::OAuthError::Timeout)));
+
+    // The listener is closed again once the server gives up.
+    assert!(std::net::TcpListener::bind(("127.0.0.1", port)).is_ok());
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_oauth_bind() {
+    let oauth = Oauth::bind("client", RedirectPort::Ephemeral).unwrap();
+    assert_ne!(oauth.port(), 0);
+    assert!(oauth
+        .url()
+        .contains(&format!("redirect_uri=http://localhost:{}&", oauth.port())));
+
+    let busy = oauth.port();
+    assert!(matches!(
+        Oauth::bind("client", RedirectPort::Fixed(busy)),
+        Err(errors::OAuthError::BindError(_))
+    ));
+    let next = Oauth::bind(
+        "client",
+        RedirectPort::Range(busy..=busy.saturating_add(20)),
+    )
+    .unwrap();
+    assert_ne!(next.port(), busy);
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_minecraft_profile() {
+    let profile: MinecraftProfile = serde_json::from_str(
+        r#"{
+            "id": "069a79f444e94726a5befca90e38aaf5",
+            "name": "Notch",
+            "skins": [{
+                "id": "6a6e65e5-76dd-4c3c-a625-162924514568",
+                "state": "ACTIVE",
+                "url": "http://textures.minecraft.net/texture/292009a4925b58f02c77dadc3ecef07ea4c7472f64e0fdc32ce5522489362680",
+                "textureKey": "292009a4925b58f02c77dadc3ecef07ea4c7472f64e0fdc32ce5522489362680",
+                "variant": "CLASSIC"
+            }],
+            "capes": []
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(profile.id, "069a79f444e94726a5befca90e38aaf5");
+    assert_eq!(profile.name, "Notch");
+    assert_eq!(profile.skins[0].variant, "CLASSIC");
+    assert!(profile.capes.is_empty());
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_entitlements() {
+    let entitlements: Entitlements = serde_json::from_str(
+        r#"{
+            "items": [
+                {"name": "product_minecraft", "signature": "jwt"},
+                {"name": "game_minecraft", "signature": "jwt"},
+                {"name": "product_game_pass_ultimate", "signature": "jwt"}
+            ],
+            "signature": "jwt",
+            "keyId": "1"
+        }"#,
+    )
+    .unwrap();
+    assert!(entitlements.owns_java());
+    assert!(entitlements.game_pass());
+    assert_eq!(entitlements.items[1].kind(), EntitlementKind::GameMinecraft);
+
+    let entitlements: Entitlements =
+        serde_json::from_str(r#"{"items": [], "signature": "jwt", "keyId": "1"}"#).unwrap();
+    assert!(!entitlements.owns_java());
+}
+
+#[test]
+fn test_xsts_error_codes() {
+    use errors::XTSError;
+
+    assert!(matches!(
+        XTSError::from_code(2_148_916_233),
+        XTSError::NoXboxProfile
+    ));
+    assert!(matches!(
+        XTSError::from_code(2_148_916_237),
+        XTSError::AdultVerification
+    ));
+    assert!(matches!(
+        XTSError::from_code(2_148_916_238),
+        XTSError::ChildAccount
+    ));
+    assert!(matches!(XTSError::from_code(1), XTSError::Refused(1)));
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_auth_error_stage() {
+    use errors::{AuthError, AuthStage, TokenError, XTSError};
+
+    let error = AuthError::from(TokenError::Status {
+        status: 400,
+        body: "invalid_grant".to_string(),
+    });
+    assert_eq!(error.stage(), AuthStage::Token);
+    assert_eq!(error.status(), Some(400));
+    assert_eq!(error.body(), Some("invalid_grant"));
+
+    let error = AuthError::from(XTSError::NoXboxProfile);
+    assert_eq!(error.stage(), AuthStage::Xsts);
+    assert_eq!(error.status(), None);
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_auth_config_endpoints() {
+    let config = AuthConfig::new().endpoints(Endpoints::all("http://127.0.0.1:9000/"));
+    assert_eq!(config.endpoints.xbox_xsts, "http://127.0.0.1:9000");
+
+    let oauth = Oauth::new("client", None).config(config);
+    assert!(oauth
+        .url()
+        .starts_with("http://127.0.0.1:9000/consumers/oauth2/v2.0/authorize/?client_id=client&"));
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[test]
+fn test_account_store() {
+    use errors::StoreError;
+
+    let dir = std::env::temp_dir().join(format!("minecraft-essentials-{}", oauth::state()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let session = AuthSession {
+        access_token: "token".to_string(),
+        uuid: "uuid".to_string(),
+        profile: MinecraftProfile::default(),
+        xbox_identity: None,
+        entitlements: Entitlements::default(),
+        refresh_token: "refresh".to_string(),
+        token_kind: xbox::TokenKind::Azure,
+        tenant: "consumers".to_string(),
+        scope: "XboxLive.signin XboxLive.offline_access".to_string(),
+        msa_expires_at: 1,
+        xbl_expires_at: 2,
+        xsts_expires_at: 3,
+        minecraft_expires_at: 4,
+    };
+
+    let key_file = dir.join("store.key");
+    AccountStore::generate_key_file(&key_file).unwrap();
+    assert!(AccountStore::generate_key_file(&key_file).is_err());
+    let store = AccountStore::new(dir.join("keyed.json"), StoreKey::KeyFile(key_file));
+    assert!(store.load().unwrap().is_empty());
+    store.save(&[session.clone()]).unwrap();
+    assert_eq!(store.load().unwrap(), vec![session.clone()]);
+    assert!(!std::fs::read_to_string(store.path())
+        .unwrap()
+        .contains("refresh"));
+
+    let path = dir.join("passphrase.json");
+    let store = AccountStore::new(&path, StoreKey::Passphrase("correct".to_string()));
+    assert!(!format!("{store:?}").contains("correct"));
+    store.save(&[session.clone()]).unwrap();
+    let salt = |path: &std::path::Path| {
+        let envelope: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        envelope["kdf"]["salt"].as_str().unwrap().to_string()
+    };
+    let first_salt = salt(&path);
+    // Later writes reuse the derived key, and with it the salt.
+    store.save(&[session.clone()]).unwrap();
+    assert_eq!(salt(&path), first_salt);
+    assert_eq!(store.load().unwrap(), vec![session.clone()]);
+    let reopened = AccountStore::new(&path, StoreKey::Passphrase("correct".to_string()));
+    assert_eq!(reopened.load().unwrap(), vec![session]);
+    let wrong = AccountStore::new(&path, StoreKey::Passphrase("wrong".to_string()));
+    assert!(matches!(wrong.load(), Err(StoreError::Decrypt)));
+
+    let original = std::fs::read_to_string(&path).unwrap();
+    let stalling = original.replace("\"iterations\": 600000", "\"iterations\": 4294967295");
+    assert_ne!(stalling, original);
+    std::fs::write(&path, stalling).unwrap();
+    assert!(matches!(store.load(), Err(StoreError::Format(_))));
+    std::fs::write(&path, original).unwrap();
+
+    let newer = std::fs::read_to_string(&path)
+        .unwrap()
+        .replace("\"version\": 1", "\"version\": 2");
+    std::fs::write(&path, newer).unwrap();
+    assert!(matches!(
+        store.load(),
+        Err(StoreError::UnsupportedVersion(2))
+    ));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[tokio::test]
+async fn test_account_manager_mock() {
+    use errors::AccountError;
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let dir = std::env::temp_dir().join(format!("minecraft-essentials-{}", oauth::state()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let key = StoreKey::KeyFile(dir.join("store.key"));
+    AccountStore::generate_key_file(dir.join("store.key")).unwrap();
+    let store = AccountStore::new(dir.join("accounts.json"), key);
+
+    let mut manager = AccountManager::open(store.clone(), "client")
+        .unwrap()
+        .config(server.config());
+    assert!(matches!(
+        manager.get_valid_session().await,
+        Err(AccountError::NoActiveAccount)
+    ));
+
+    let code = DeviceCode::with_config("client", server.config().xbox_identity(true))
+        .await
+        .unwrap();
+    let account = manager.add_device_code(&code).await.unwrap();
+    assert_eq!(account.name, MockServer::PROFILE_NAME);
+    assert_eq!(account.gamertag.as_deref(), Some(MockServer::GAMERTAG));
+    assert!(account.active);
+    assert!(!format!("{manager:?}").contains(MockServer::MINECRAFT_TOKEN));
+
+    let mut other = manager.active().unwrap().clone();
+    other.uuid = "other".to_string();
+    let mut expired = manager.active().unwrap().clone();
+    expired.minecraft_expires_at = 0;
+    manager.add(expired).unwrap();
+    assert!(!manager.add(other).unwrap().active);
+    manager.set_active("other").unwrap();
+    assert!(manager.set_active("unknown").is_err());
+
+    // Reopening the store keeps the active account, as does saving the sessions directly.
+    store.save(&store.load().unwrap()).unwrap();
+    let mut manager = AccountManager::open(store, "client")
+        .unwrap()
+        .config(server.config());
+    assert_eq!(manager.accounts().len(), 2);
+    assert_eq!(manager.active().unwrap().uuid, "other");
+    manager.set_active(MockServer::PROFILE_ID).unwrap();
+
+    let requests = server.requests().len();
+    let session = manager.get_valid_session().await.unwrap();
+    assert!(!session.needs_refresh(std::time::Duration::from_secs(300)));
+    assert!(server.requests().len() > requests);
+    let account = &manager.accounts()[0];
+    assert_eq!(account.uuid, MockServer::PROFILE_ID);
+    assert_eq!(account.gamertag.as_deref(), Some(MockServer::GAMERTAG));
+
+    assert!(manager.remove(MockServer::PROFILE_ID).unwrap());
+    assert!(!manager.remove(MockServer::PROFILE_ID).unwrap());
+    assert_eq!(manager.accounts().len(), 1);
+    assert!(manager.active().is_none());
+
+    // Sessions from `login.live.com` are refreshed there rather than with the manager's client ID.
+    let login = LiveLogin::new().config(server.config());
+    let redirect = format!(
+        "{}?code={}&state={}",
+        login.redirect_uri(),
+        MockServer::CODE,
+        login.state
+    );
+    let mut live = login.launch_session(&redirect).await.unwrap();
+    live.minecraft_expires_at = 0;
+    manager.add(live).unwrap();
+    manager.set_active(MockServer::PROFILE_ID).unwrap();
+    let requests = server.requests().len();
+    let session = manager.get_valid_session().await.unwrap();
+    assert_eq!(session.token_kind, xbox::TokenKind::Live);
+    let refresh = &server.requests()[requests..];
+    assert!(refresh
+        .iter()
+        .any(|request| request == "POST /oauth20_token.srf"));
+    assert!(!refresh
+        .iter()
+        .any(|request| request.ends_with("/oauth2/v2.0/token")));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[tokio::test]
+async fn test_account_manager_tenant_mock() {
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let dir = std::env::temp_dir().join(format!("minecraft-essentials-{}", oauth::state()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let key = StoreKey::KeyFile(dir.join("store.key"));
+    AccountStore::generate_key_file(dir.join("store.key")).unwrap();
+    let store = AccountStore::new(dir.join("accounts.json"), key);
+    let mut manager = AccountManager::open(store.clone(), "client")
+        .unwrap()
+        .config(server.config());
+
+    let oauth = Oauth::builder("client")
+        .tenant("common")
+        .scope("openid")
+        .bind(RedirectPort::Ephemeral)
+        .unwrap()
+        .config(server.config());
+    let callback = format!(
+        "http://127.0.0.1:{}/?code={}&state={}",
+        oauth.port(),
+        MockServer::CODE,
+        oauth.state
+    );
+    let browser = tokio::spawn(reqwest::get(callback));
+    manager.add_oauth(&oauth).await.unwrap();
+    browser.await.unwrap().unwrap();
+    assert_eq!(manager.active().unwrap().tenant, "common");
+
+    // The session is refreshed with the tenant and scopes it was signed in with, even after reopening the store.
+    let mut session = manager.active().unwrap().clone();
+    session.minecraft_expires_at = 0;
+    manager.add(session).unwrap();
+    let mut manager = AccountManager::open(store, "client")
+        .unwrap()
+        .config(server.config());
+    let requests = server.requests().len();
+    let session = manager.get_valid_session().await.unwrap();
+    assert!(server.requests()[requests..]
+        .iter()
+        .any(|request| request == "POST /common/oauth2/v2.0/token"));
+    assert_eq!(session.tenant, "common");
+    assert!(session.scope.ends_with(" openid"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "custom-auth")]
+#[test]
+fn test_offline_account() {
+    use errors::OfflineError;
+
+    let offline = Offline::new("Notch").unwrap();
+    assert_eq!(offline.uuid(), "b50ad385829d3141a2167e7d7539ba7f");
+    let auth = offline.launch();
+    assert_eq!(auth.uuid, offline.uuid());
+    assert_eq!(auth.profile.name, "Notch");
+
+    assert!(Offline::new("a_B_9").is_ok());
+    assert_eq!(
+        Offline::new("ab").err(),
+        Some(OfflineError::InvalidLength(2))
+    );
+    assert_eq!(
+        Offline::new("abcdefghijklmnopq").err(),
+        Some(OfflineError::InvalidLength(17))
+    );
+    assert_eq!(
+        Offline::new("bad name").err(),
+        Some(OfflineError::InvalidCharacter(' '))
+    );
+    assert_eq!(
+        Offline::new("éééééééééé").err(),
+        Some(OfflineError::InvalidCharacter('é'))
+    );
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_bedrock_launch_mock() {
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let device_code = DeviceCode::with_config("client", server.config())
+        .await
+        .unwrap();
+    let auth = device_code.launch_bedrock().await.unwrap();
+
+    assert_eq!(auth.xsts_token, MockServer::XSTS_TOKEN);
+    assert_eq!(auth.user_hash, MockServer::USER_HASH);
+    assert_eq!(auth.xuid.as_deref(), Some(MockServer::XUID));
+    assert_eq!(auth.gamertag.as_deref(), Some(MockServer::GAMERTAG));
+    assert_eq!(
+        auth.expires_at,
+        xbox::timestamp("2124-05-02T20:09:16Z").unwrap()
+    );
+    assert_eq!(
+        auth.authorization_header,
+        format!(
+            "XBL3.0 x={};{}",
+            MockServer::USER_HASH,
+            MockServer::XSTS_TOKEN
+        )
+    );
+    assert!(!server
+        .requests()
+        .iter()
+        .any(|request| request.contains("minecraft")));
+}
+
+#[cfg(feature = "custom-auth")]
+#[tokio::test]
+async fn test_yggdrasil_mock() {
+    use errors::YggdrasilError;
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let yggdrasil = Yggdrasil::new(&server.url(), "client")
+        .config(server.config())
+        .discover()
+        .await
+        .unwrap();
+    assert_eq!(yggdrasil.api_root(), format!("{}/yggdrasil", server.url()));
+
+    let auth = yggdrasil
+        .authenticate(MockServer::USERNAME, MockServer::PASSWORD)
+        .await
+        .unwrap();
+    assert_eq!(auth.access_token, MockServer::YGGDRASIL_TOKEN);
+    assert_eq!(auth.uuid, MockServer::PROFILE_ID);
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+
+    let refreshed = yggdrasil.refresh(&auth.access_token).await.unwrap();
+    assert_eq!(refreshed.uuid, MockServer::PROFILE_ID);
+    assert!(yggdrasil.validate(&auth.access_token).await.unwrap());
+    assert!(!yggdrasil.validate("revoked").await.unwrap());
+    yggdrasil.invalidate(&auth.access_token).await.unwrap();
+    yggdrasil
+        .signout(MockServer::USERNAME, MockServer::PASSWORD)
+        .await
+        .unwrap();
+
+    let err = yggdrasil
+        .authenticate(MockServer::USERNAME, "wrong")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, YggdrasilError::Status { status: 403, .. }));
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[test]
+fn test_server_hash() {
+    assert_eq!(
+        SessionServer::server_hash("Notch", &[], &[]),
+        "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+    );
+    assert_eq!(
+        SessionServer::server_hash("jeb_", &[], &[]),
+        "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+    );
+    assert_eq!(
+        SessionServer::server_hash("sim", b"o", b"n"),
+        "88e16a1019277b15d58faf0541e11910eb756f6"
+    );
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[tokio::test]
+async fn test_session_server_mock() {
+    use errors::SessionError;
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let auth = mock_oauth_launch(&server).await.unwrap();
+    let session_server = SessionServer::new().config(server.config());
+    let hash = SessionServer::server_hash("", b"secret", b"key");
+
+    assert_eq!(
+        session_server
+            .has_joined(MockServer::PROFILE_NAME, &hash, None)
+            .await
+            .unwrap(),
+        None
+    );
+    session_server.join(&auth, &hash).await.unwrap();
+    let profile = session_server
+        .has_joined(
+            MockServer::PROFILE_NAME,
+            &hash,
+            Some("127.0.0.1".parse().unwrap()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(profile.id, MockServer::PROFILE_ID);
+    assert_eq!(profile.properties[0].name, "textures");
+
+    let mut expired = auth.clone();
+    expired.access_token = "expired".to_string();
+    let err = session_server.join(&expired, &hash).await.unwrap_err();
+    assert!(matches!(err, SessionError::Status { status: 403, .. }));
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[tokio::test]
+async fn test_sisu_mock() {
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let key = ProofKey::generate().unwrap();
+    let key = ProofKey::from_pkcs8(key.pkcs8()).unwrap();
+    assert_eq!(key.jwk()["crv"], "P-256");
+    let sisu = Sisu::new(key).config(server.config());
+
+    let device_token = sisu.device_token().await.unwrap();
+    assert_eq!(device_token.token, MockServer::DEVICE_TOKEN);
+    assert_eq!(
+        device_token.expires_at,
+        xbox::timestamp("2124-05-02T20:09:16Z").unwrap()
+    );
+
+    let auth = sisu
+        .authorize(
+            MockServer::MICROSOFT_TOKEN,
+            xbox::TokenKind::Azure,
+            "client",
+            &device_token,
+            "https://pocket.realms.minecraft.net/",
+        )
+        .await
+        .unwrap();
+    assert_eq!(auth.xsts_token, MockServer::XSTS_TOKEN);
+    assert_eq!(auth.gamertag.as_deref(), Some(MockServer::GAMERTAG));
+
+    let auth = sisu
+        .authorize(
+            MockServer::LIVE_TOKEN,
+            xbox::TokenKind::Live,
+            "00000000402b5328",
+            &device_token,
+            "https://pocket.realms.minecraft.net/",
+        )
+        .await
+        .unwrap();
+    assert_eq!(auth.xsts_token, MockServer::XSTS_TOKEN);
+
+    // Live tokens passed as if an Azure app had issued them are refused.
+    let error = sisu
+        .authorize(
+            MockServer::LIVE_TOKEN,
+            xbox::TokenKind::Azure,
+            "00000000402b5328",
+            &device_token,
+            "rp",
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), Some(400));
+
+    let stale = DeviceToken {
+        token: "stale".to_string(),
+        expires_at: 0,
+    };
+    let error = sisu
+        .authorize(
+            MockServer::MICROSOFT_TOKEN,
+            xbox::TokenKind::Azure,
+            "client",
+            &stale,
+            "rp",
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), Some(400));
+}
+
+#[cfg(feature = "minecraft-auth")]
+#[tokio::test]
+async fn test_live_login_mock() {
+    use errors::{AuthError, OAuthError};
+    use mock::MockServer;
+
+    let server = MockServer::start().await.unwrap();
+    let login = LiveLogin::new().config(server.config());
+    assert!(login.url().starts_with(&format!(
+        "{}/oauth20_authorize.srf?client_id=00000000402b5328&",
+        server.url()
+    )));
+
+    let redirect = format!(
+        "{}?code={}&state={}",
+        login.redirect_uri(),
+        MockServer::CODE,
+        login.state
+    );
+    assert!(login.is_redirect(&redirect));
+    assert!(!login.is_redirect(login.url()));
+
+    let auth = login.launch(&redirect).await.unwrap();
+    assert_eq!(auth.access_token, MockServer::MINECRAFT_TOKEN);
+    assert_eq!(auth.profile.name, MockServer::PROFILE_NAME);
+
+    let (auth, refresh_token) = login.refresh(MockServer::REFRESH_TOKEN).await.unwrap();
+    assert_eq!(auth.uuid, MockServer::PROFILE_ID);
+    assert_eq!(refresh_token, MockServer::REFRESH_TOKEN);
+
+    let session = login.launch_session(&redirect).await.unwrap();
+    assert_eq!(session.token_kind, xbox::TokenKind::Live);
+    let session = login.refresh_session(&session).await.unwrap();
+    assert_eq!(session.token_kind, xbox::TokenKind::Live);
+    assert_eq!(session.access_token, MockServer::MINECRAFT_TOKEN);
+
+    let denied = format!(
+        "{}?error=access_denied&error_description=Denied&state={}",
+        login.redirect_uri(),
+        login.state
+    );
+    assert!(matches!(
+        login.launch(&denied).await,
+        Err(AuthError::OAuth(OAuthError::AuthenticationFailure(_)))
+    ));
+    let forged = format!(
+        "{}?code={}&state=forged",
+        login.redirect_uri(),
+        MockServer::CODE
+    );
+    assert!(matches!(
+        login.launch(&forged).await,
+        Err(AuthError::OAuth(OAuthError::StateMismatch))
+    ));
+}